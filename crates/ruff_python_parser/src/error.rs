@@ -3,6 +3,7 @@ use std::fmt::{self, Display};
 use ruff_python_ast::PythonVersion;
 use ruff_text_size::{Ranged, TextRange};
 
+use crate::semantic_errors::SemanticSyntaxErrorKind;
 use crate::{TokenKind, string::InterpolatedStringKind};
 
 /// Represents represent errors that occur during parsing and are
@@ -100,6 +101,81 @@ impl std::fmt::Display for InterpolatedStringErrorType {
     }
 }
 
+/// A bash construct that was written in xonsh, along with the xonsh code that replaces it.
+#[derive(Debug, Clone, PartialEq, Eq, get_size2::GetSize)]
+pub struct ShellSyntaxError {
+    pub kind: ShellSyntaxKind,
+    /// The xonsh code to replace the range of the error with.
+    pub fix: String,
+}
+
+impl std::fmt::Display for ShellSyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}, use `{}` instead", self.kind, self.fix)
+    }
+}
+
+/// Represents the bash constructs that are detected in xonsh code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, get_size2::GetSize)]
+pub enum ShellSyntaxKind {
+    /// `export NAME=value`
+    Export,
+    /// `if [ -f x ]; then`
+    TestCondition,
+    /// `$((1 + 2))`
+    ArithmeticExpansion,
+    /// `${NAME:-default}`
+    DefaultValue,
+    /// `for f in *.txt; do`
+    ForDo,
+}
+
+impl std::fmt::Display for ShellSyntaxKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Export => {
+                f.write_str("`export` is bash syntax for setting an environment variable")
+            }
+            Self::TestCondition => f.write_str("`[ ... ]; then` is a bash condition"),
+            Self::ArithmeticExpansion => f.write_str("`$((...))` is bash arithmetic"),
+            Self::DefaultValue => {
+                f.write_str("`${NAME:-default}` is bash syntax for a default value")
+            }
+            Self::ForDo => f.write_str("`for ...; do` is a bash loop"),
+        }
+    }
+}
+
+/// Represents the errors in the static parts of regex and glob literals, like `` `(a` `` or
+/// `g"[b-a]"`.
+#[derive(Debug, Clone, PartialEq, Eq, get_size2::GetSize)]
+pub enum PatternErrorType {
+    /// A group without its closing `)`, e.g. `` `(a` ``.
+    UnterminatedGroup,
+    /// A `)` without an opening `(`, e.g. `` `a)` ``.
+    UnbalancedParenthesis,
+    /// An escape that the regex engine rejects, e.g. `` `\q` ``.
+    BadEscape(String),
+    /// A character class without its closing `]`, e.g. `` `[a-z` ``.
+    UnterminatedCharacterSet,
+    /// A range in a character class whose start comes after its end, e.g. `` `[z-a]` ``.
+    BadCharacterRange(char, char),
+}
+
+impl std::fmt::Display for PatternErrorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::UnterminatedGroup => f.write_str("missing `)`, unterminated group"),
+            Self::UnbalancedParenthesis => f.write_str("unbalanced parenthesis"),
+            Self::BadEscape(escape) => write!(f, "bad escape `{escape}`"),
+            Self::UnterminatedCharacterSet => f.write_str("unterminated character set"),
+            Self::BadCharacterRange(start, end) => {
+                write!(f, "bad character range `{start}-{end}`")
+            }
+        }
+    }
+}
+
 /// Represents the different types of errors that can occur during parsing.
 #[derive(Debug, PartialEq, Eq, Clone, get_size2::GetSize)]
 pub enum ParseErrorType {
@@ -198,6 +274,53 @@ pub enum ParseErrorType {
     /// An unexpected token was found at the end of an expression parsing
     UnexpectedExpressionToken,
 
+    /// A `$` was not followed by an environment variable name, e.g. `$1`.
+    InvalidEnvironmentVariableName(TokenKind),
+    /// An environment expression without an expression was found, e.g. `${}`.
+    EmptyEnvironmentExpression,
+    /// A subprocess without its closing token was found, e.g. `$(ls`.
+    ///
+    /// The closing token is [`TokenKind::Newline`] for a bracket left open in a bare command.
+    UnclosedSubprocess(TokenKind),
+    /// A `with!` suite with a bracket left open was found.
+    UnclosedWithMacroSuite,
+    /// A token that doesn't end a `with!` suite was found where it should end.
+    InvalidWithMacroSuite(TokenKind),
+    /// A token that isn't a command argument was found before the given closing token of a
+    /// subprocess, which is [`TokenKind::Newline`] for a bare command.
+    ExpectedCommandArgument(TokenKind),
+    /// A token that isn't an expression was found in an environment expression, e.g. `${)}`.
+    InvalidEnvironmentExpression(TokenKind),
+    /// A redirection without a target was found, e.g. `$(ls >)`.
+    MissingRedirectTarget,
+    /// A `&` in a redirect target was not followed by a file descriptor, e.g. `$(ls 2>&x)`.
+    ExpectedFileDescriptor,
+    /// A file descriptor was duplicated by a redirect other than `>`, e.g. `$(ls 2>>&1)`.
+    InvalidRedirectDuplication,
+    /// Commands chained with `&&` or `||` were found in a process substitution, e.g.
+    /// `diff <(a && b) c`.
+    ChainInProcessSubstitution(TokenKind),
+    /// An `@` was not followed by a `(` or a search function in a subprocess, e.g. `$(ls @x)`.
+    InvalidInterpolation(TokenKind),
+    /// A modifier other than the known ones was found before a command, e.g. `$(@yaml cat x)`.
+    UnknownSpecModifier(String),
+    /// A modifier was found after the start of a command, e.g. `$(curl @json x)`.
+    MisplacedSpecModifier(String),
+    /// A token that doesn't start a macro argument was found, like the second `,` of an empty
+    /// argument in `f!(x, , y)`.
+    InvalidMacroArgument(TokenKind),
+    /// A `&` was found before the end of a command, e.g. `$(sleep 1 & ls)`.
+    BackgroundNotAtEnd,
+    /// A bash construct was found, e.g. `export FOO=bar`.
+    ShellSyntax(ShellSyntaxError),
+    /// An invalid regex literal was found, e.g. `` `(a` ``.
+    InvalidRegex(PatternErrorType),
+    /// An invalid glob literal was found, e.g. `g"[a-z"`.
+    InvalidGlob(PatternErrorType),
+    /// An error found by the [`crate::semantic_errors::SemanticSyntaxChecker`] after parsing,
+    /// e.g. `return` outside of a function.
+    SemanticError(SemanticSyntaxErrorKind),
+
     /// An f-string error containing the [`InterpolatedStringErrorType`].
     FStringError(InterpolatedStringErrorType),
     /// A t-string error containing the [`InterpolatedStringErrorType`].
@@ -207,6 +330,8 @@ pub enum ParseErrorType {
 
     /// Parser aborted because [`crate::ParseOptions::max_recursion_depth`] was exceeded.
     RecursionLimitExceeded,
+    /// The parser panicked with the given message, which is a bug in the parser.
+    InternalError(String),
 }
 
 impl ParseErrorType {
@@ -339,6 +464,73 @@ impl std::fmt::Display for ParseErrorType {
                 write!(f, "Unexpected token at the end of an expression")
             }
             ParseErrorType::RecursionLimitExceeded => f.write_str("Source is too deeply nested"),
+            ParseErrorType::InternalError(msg) => write!(f, "Internal parser error: {msg}"),
+            ParseErrorType::InvalidEnvironmentVariableName(found) => {
+                write!(f, "Expected an environment variable name, found {found}")
+            }
+            ParseErrorType::EmptyEnvironmentExpression => {
+                f.write_str("Expected an environment variable name or expression")
+            }
+            ParseErrorType::UnclosedSubprocess(TokenKind::Newline) => {
+                f.write_str("Expected a closing bracket before the end of the command")
+            }
+            ParseErrorType::UnclosedSubprocess(closing) => {
+                write!(f, "Expected {closing} to close the subprocess")
+            }
+            ParseErrorType::UnclosedWithMacroSuite => {
+                f.write_str("Expected a closing bracket before the end of the `with!` suite")
+            }
+            ParseErrorType::InvalidWithMacroSuite(found) => {
+                write!(f, "Expected the end of the `with!` suite, found {found}")
+            }
+            ParseErrorType::ExpectedCommandArgument(TokenKind::Newline) => {
+                f.write_str("Expected a command argument or the end of the line")
+            }
+            ParseErrorType::ExpectedCommandArgument(closing) => {
+                write!(f, "Expected a command argument or {closing}")
+            }
+            ParseErrorType::InvalidEnvironmentExpression(found) => {
+                write!(f, "Expected an expression or '}}', found {found}")
+            }
+            ParseErrorType::MissingRedirectTarget => f.write_str("Expected a redirection target"),
+            ParseErrorType::ExpectedFileDescriptor => {
+                f.write_str("Expected a file descriptor number after `&` in a redirection")
+            }
+            ParseErrorType::InvalidRedirectDuplication => {
+                f.write_str("Only `>` can redirect to a file descriptor like `&1`")
+            }
+            ParseErrorType::ChainInProcessSubstitution(found) => {
+                write!(
+                    f,
+                    "Expected a single pipeline in a process substitution, found {found}"
+                )
+            }
+            ParseErrorType::InvalidInterpolation(found) => {
+                write!(
+                    f,
+                    "Expected `(` or a search function after `@`, found {found}"
+                )
+            }
+            ParseErrorType::UnknownSpecModifier(name) => {
+                write!(
+                    f,
+                    "Unknown subprocess modifier `@{name}`, expected one of `@json`, `@lines`, \
+                     `@path`, `@paths`, `@unthread` or `@error_raise`"
+                )
+            }
+            ParseErrorType::MisplacedSpecModifier(name) => {
+                write!(f, "The `@{name}` modifier must come before the command")
+            }
+            ParseErrorType::InvalidMacroArgument(found) => {
+                write!(f, "Expected a macro argument, found {found}")
+            }
+            ParseErrorType::BackgroundNotAtEnd => {
+                f.write_str("`&` is only allowed at the end of a command")
+            }
+            ParseErrorType::ShellSyntax(error) => write!(f, "{error}"),
+            ParseErrorType::InvalidRegex(error) => write!(f, "regex: {error}"),
+            ParseErrorType::InvalidGlob(error) => write!(f, "glob: {error}"),
+            ParseErrorType::SemanticError(kind) => write!(f, "{kind}"),
         }
     }
 }
@@ -427,6 +619,8 @@ pub enum LexicalErrorType {
     InvalidByteLiteral,
     /// An unexpected character was encountered after a line continuation.
     LineContinuationError,
+    /// String prefix letters that can't be combined, like `pb` or `bu`.
+    InvalidStringPrefix(Box<str>),
     /// An unexpected end of file was encountered.
     Eof,
     /// An unexpected error occurred.
@@ -466,6 +660,9 @@ impl std::fmt::Display for LexicalErrorType {
             Self::LineContinuationError => {
                 write!(f, "Expected a newline after line continuation character")
             }
+            Self::InvalidStringPrefix(prefix) => {
+                write!(f, "Invalid string prefix `{prefix}`")
+            }
             Self::Eof => write!(f, "unexpected EOF while parsing"),
             Self::OtherError(msg) => write!(f, "{msg}"),
             Self::UnclosedStringError => {
@@ -518,9 +518,15 @@ impl<'src> Lexer<'src> {
             '@' => {
                 if self.cursor.eat_char('=') {
                     TokenKind::AtEqual
-                } else if self.cursor.eat_char('$') && self.cursor.eat_char('(') {
+                } else if self.cursor.eat_char2('$', '(') {
                     self.nesting += 1;
                     TokenKind::AtDollarLParen
+                } else if self.cursor.first() == '.'
+                    && (is_ascii_identifier_start(self.cursor.second())
+                        || is_unicode_identifier_start(self.cursor.second()))
+                {
+                    self.cursor.bump();
+                    TokenKind::AtDot
                 } else {
                     TokenKind::At
                 }
@@ -667,26 +673,38 @@ impl<'src> Lexer<'src> {
 
     /// Lex an identifier. Also used for keywords and string/bytes literals with a prefix.
     fn lex_identifier(&mut self, first: char) -> TokenKind {
-        // Detect potential string like rb'' b'' f'' t'' u'' r''
-        let quote = if let Some(prefix) = single_char_prefix(first) {
-            match self.cursor.first() {
-                quote @ ('\'' | '"' | '`') => {
-                    self.current_flags |= prefix;
+        // Detect potential string like rb'' b'' f'' t'' u'' r'', or xonsh ones like pf'' or pg``
+        let quote = if single_char_prefix(first).is_some() {
+            let rest = self.cursor.rest();
+            let len = rest
+                .chars()
+                .take(2)
+                .take_while(|&c| single_char_prefix(c).is_some())
+                .count();
+            // The prefix letters are ASCII, so `len` is also their length in bytes.
+            rest[len..]
+                .chars()
+                .next()
+                .filter(|&quote| is_quote(quote))
+                .map(|quote| {
+                    self.cursor.skip_bytes(len);
+                    let prefix = self.token_text();
+                    match string_prefix_flags(prefix, quote) {
+                        Some(flags) => self.current_flags |= flags,
+                        None => {
+                            // Lex it as a plain string to recover.
+                            self.errors.push(LexicalError::new(
+                                LexicalErrorType::InvalidStringPrefix(prefix.into()),
+                                self.token_range(),
+                            ));
+                            if prefix.contains(['r', 'R']) {
+                                self.current_flags |= TokenFlags::RAW_STRING_LOWERCASE;
+                            }
+                        }
+                    }
                     self.cursor.bump();
-                    Some(quote)
-                }
-                second
-                    if let quote = self.cursor.second()
-                        && is_quote(quote) =>
-                {
-                    self.try_double_char_prefix([first, second]).then(|| {
-                        self.cursor.bump();
-                        self.cursor.bump();
-                        quote
-                    })
-                }
-                _ => None,
-            }
+                    quote
+                })
         } else {
             None
         };
@@ -769,30 +787,6 @@ impl<'src> Lexer<'src> {
         }
     }
 
-    /// Try lexing a single character string prefix, updating the token flags accordingly.
-    /// Returns `true` if it matches.
-    fn try_single_char_prefix(&mut self, value: char) -> bool {
-        let Some(prefix) = single_char_prefix(value) else {
-            return false;
-        };
-
-        self.current_flags |= prefix;
-        true
-    }
-
-    /// Try lexing the double character string prefix, updating the token flags accordingly.
-    /// Returns `true` if it matches.
-    fn try_double_char_prefix(&mut self, value: [char; 2]) -> bool {
-        let checkpoint = self.current_flags;
-
-        if value.into_iter().all(|c| self.try_single_char_prefix(c)) {
-            true
-        } else {
-            self.current_flags = checkpoint;
-            false
-        }
-    }
-
     /// Lex a f-string or t-string start token if positioned at the start of an f-string or t-string.
     fn lex_interpolated_string_start(&mut self, quote: char) -> Option<TokenKind> {
         #[cfg(debug_assertions)]
@@ -800,6 +794,8 @@ impl<'src> Lexer<'src> {
 
         if quote == '"' {
             self.current_flags |= TokenFlags::DOUBLE_QUOTES;
+        } else if quote == '`' {
+            self.current_flags |= TokenFlags::BACKTICK_STRING;
         }
 
         if self.cursor.eat_char2(quote, quote) {
@@ -900,7 +896,7 @@ impl<'src> Lexer<'src> {
                         self.cursor.bump();
                     }
                 }
-                quote @ ('\'' | '"') if quote == interpolated_string.quote_char() => {
+                quote @ ('\'' | '"' | '`') if quote == interpolated_string.quote_char() => {
                     if let Some(triple_quotes) = interpolated_string.triple_quotes() {
                         if self.cursor.rest().starts_with(triple_quotes) {
                             break;
@@ -1415,6 +1411,145 @@ impl<'src> Lexer<'src> {
         self.cursor.skip_bytes(self.current_range.end().to_usize());
     }
 
+    /// Re-lexes a `<` or `>` that is directly followed by a `(` as the start of a process
+    /// substitution, which is only valid in the arguments of a command.
+    ///
+    /// ```xsh
+    /// diff <(sort a) <(sort b)
+    /// ```
+    ///
+    /// Returns `true` if the current token was re-lexed as [`TokenKind::LessLpar`] or
+    /// [`TokenKind::GreaterLpar`].
+    pub(crate) fn re_lex_process_substitution(&mut self) -> bool {
+        let kind = match self.current_kind {
+            TokenKind::Less => TokenKind::LessLpar,
+            TokenKind::Greater => TokenKind::GreaterLpar,
+            _ => return false,
+        };
+        if !self.source[self.current_range.end().to_usize()..].starts_with('(') {
+            return false;
+        }
+
+        self.current_range = TextRange::at(self.current_range.start(), "<(".text_len());
+        self.current_kind = kind;
+        self.nesting += 1;
+        self.state = State::Other;
+        self.cursor = Cursor::new(self.source);
+        self.cursor.skip_bytes(self.current_range.end().to_usize());
+        true
+    }
+
+    /// Re-lexes the current token as the start of a shell word in the arguments of a command.
+    /// The word goes on to the next whitespace, operator like `|` or `>`, or xonsh expression
+    /// like `$HOME` or `@(x)`, or to the given closing token of the command, if it isn't inside
+    /// brackets of the word.
+    ///
+    /// ```xsh
+    /// ls --version=1.2.3 a#b it's 08 a\|b
+    /// ```
+    ///
+    /// A backslash escapes the next character. A quote only ends the word if it's closed on the
+    /// same line, so that the string is joined to the word like in `--msg='hi there'`, and is
+    /// part of the word otherwise.
+    pub(crate) fn re_lex_shell_word(&mut self, closing: TokenKind) {
+        let start = self.current_range.start();
+        while self
+            .errors
+            .last()
+            .is_some_and(|error| error.location().start() >= start)
+        {
+            self.errors.pop();
+        }
+
+        // The brackets of the word that are still open.
+        let mut depth = 0u32;
+        self.cursor = Cursor::new(self.source);
+        if self.current_kind == TokenKind::Unknown {
+            self.cursor.skip_bytes(start.to_usize());
+        } else {
+            // The current token, like `<<` in `cat <<EOF`, is the start of the word even if it's
+            // an operator. Its bracket has already been counted by the lexer.
+            self.cursor.skip_bytes(self.current_range.end().to_usize());
+            if matches!(self.current_kind, TokenKind::Lsqb | TokenKind::Lbrace) {
+                depth += 1;
+            }
+        }
+        let closing = match closing {
+            TokenKind::Rpar => Some(')'),
+            TokenKind::Rsqb => Some(']'),
+            _ => None,
+        };
+
+        while !self.cursor.is_eof() {
+            let c = self.cursor.first();
+            match c {
+                '\\' if !matches!(self.cursor.second(), '\n' | '\r' | EOF_CHAR) => {
+                    self.cursor.bump();
+                }
+                ' ' | '\t' | '\x0C' | '\n' | '\r' | '\\' | '|' | '&' | ';' | '<' | '>' | '$' => {
+                    break;
+                }
+                '@' if self.is_at_interpolation() => break,
+                '!' if matches!(self.cursor.second(), '(' | '[') => break,
+                '\'' | '"' | '`' if self.is_closed_on_line(c) => break,
+                '(' | '[' | '{' => {
+                    depth += 1;
+                    self.nesting += 1;
+                }
+                _ if depth == 0 && closing == Some(c) => break,
+                ')' | ']' | '}' if depth > 0 => {
+                    depth -= 1;
+                    self.nesting = self.nesting.saturating_sub(1);
+                }
+                _ => {}
+            }
+            self.cursor.bump();
+        }
+        if self.offset() == start {
+            // An error token that starts with a character that ends words, like a `\` before
+            // the end of the line, is a word by itself.
+            self.cursor.bump();
+        }
+
+        self.current_range = TextRange::new(start, self.offset());
+        self.current_kind = TokenKind::Word;
+        self.current_flags = TokenFlags::empty();
+        self.state = State::Other;
+    }
+
+    /// Returns `true` if the `@` at the cursor starts a xonsh expression, like `@(x)`, `@$(cmd)`,
+    /// `@.env` or ``@func`pattern` ``, rather than being part of a word like `user@host`.
+    fn is_at_interpolation(&self) -> bool {
+        let rest = &self.cursor.rest()['@'.len_utf8()..];
+        if rest.starts_with(['(', '.']) || rest.starts_with("$(") {
+            return true;
+        }
+        if !rest.starts_with(|c| is_ascii_identifier_start(c) || is_unicode_identifier_start(c)) {
+            return false;
+        }
+        let mut is_ascii = true;
+        let name_len = rest
+            .find(|c| !is_identifier_continuation(c, &mut is_ascii))
+            .unwrap_or(rest.len());
+        rest[name_len..].starts_with(['\'', '"', '`'])
+    }
+
+    /// Returns `true` if the quote at the cursor is closed on the same line.
+    fn is_closed_on_line(&self, quote: char) -> bool {
+        let mut chars = self.cursor.rest()[quote.len_utf8()..].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '\n' | '\r' => return false,
+                c if c == quote => return true,
+                _ => {}
+            }
+        }
+        false
+    }
+
     /// Re-lex `r"` in a format specifier position.
     ///
     /// `r"` in a format specifier position is unlikely to be the start of a raw string.
@@ -1619,6 +1754,42 @@ fn single_char_prefix(c: char) -> Option<TokenFlags> {
     })
 }
 
+/// Returns the flags of a string prefix like `rb`, `pf` or `pfr`, or `None` if its letters can't
+/// be combined.
+///
+/// On top of the Python prefixes, `p` makes a path and `g` a glob pattern. They can be combined
+/// with `r` and `f`, but not with each other, except for backtick strings, where they make a
+/// glob that returns paths. Backtick strings can't be bytes, unicode or template strings.
+fn string_prefix_flags(prefix: &str, quote: char) -> Option<TokenFlags> {
+    let mut flags = TokenFlags::empty();
+    for c in prefix.chars() {
+        let flag = single_char_prefix(c)?;
+        if flags.intersects(flag) || (flags.is_raw_string() && flag.is_raw_string()) {
+            return None;
+        }
+        flags |= flag;
+    }
+
+    let kinds = TokenFlags::F_STRING
+        | TokenFlags::T_STRING
+        | TokenFlags::BYTE_STRING
+        | TokenFlags::UNICODE_STRING;
+    if (flags & kinds).bits().count_ones() > 1
+        || (flags.intersects(TokenFlags::UNICODE_STRING) && prefix.len() > 1)
+    {
+        return None;
+    }
+    let other_kinds = TokenFlags::T_STRING | TokenFlags::BYTE_STRING | TokenFlags::UNICODE_STRING;
+    if quote == '`' {
+        return (!flags.intersects(other_kinds)).then_some(flags);
+    }
+    let xonsh = flags & (TokenFlags::PATH_STRING | TokenFlags::GLOB_STRING);
+    if xonsh.bits().count_ones() > 1 || (!xonsh.is_empty() && flags.intersects(other_kinds)) {
+        return None;
+    }
+    Some(flags)
+}
+
 const fn is_ascii_identifier_start(c: char) -> bool {
     matches!(c, 'a'..='z' | 'A'..='Z' | '_')
 }
@@ -53,13 +53,19 @@ impl InterpolatedStringContext {
 
     /// Returns the quote character for the current f-string.
     pub(crate) fn quote_char(&self) -> char {
-        self.flags.quote_style().as_char()
+        if self.flags.intersects(TokenFlags::BACKTICK_STRING) {
+            '`'
+        } else {
+            self.flags.quote_style().as_char()
+        }
     }
 
     /// Returns the triple quotes for the current f-string if it is a triple-quoted
     /// f-string, `None` otherwise.
     pub(crate) fn triple_quotes(&self) -> Option<&'static str> {
-        if self.is_triple_quoted() {
+        if self.flags.intersects(TokenFlags::BACKTICK_STRING) {
+            self.is_triple_quoted().then_some("```")
+        } else if self.is_triple_quoted() {
             Some(self.flags.quote_str())
         } else {
             None
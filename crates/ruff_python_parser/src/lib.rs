@@ -66,10 +66,10 @@ use std::iter::FusedIterator;
 use std::ops::Deref;
 
 pub use crate::error::{
-    InterpolatedStringErrorType, LexicalErrorType, ParseError, ParseErrorType,
-    UnsupportedSyntaxError, UnsupportedSyntaxErrorKind,
+    InterpolatedStringErrorType, LexicalErrorType, ParseError, ParseErrorType, PatternErrorType,
+    ShellSyntaxError, ShellSyntaxKind, UnsupportedSyntaxError, UnsupportedSyntaxErrorKind,
 };
-pub use crate::parser::ParseOptions;
+pub use crate::parser::{ParseOptions, PatternSeverity};
 pub use crate::token::{Token, TokenKind};
 
 use crate::parser::Parser;
@@ -123,6 +123,12 @@ pub fn parse_module(source: &str) -> Result<Parsed<ModModule>, ParseError> {
         .into_result()
 }
 
+/// Lexes the source code as a module, returning the tokens and the last lexical error.
+///
+/// Unlike the parser, this doesn't re-lex the arguments of commands as shell words, so it
+/// returns Python tokens (or errors) for arguments like `--version=1.2.3` or `foo\ bar`. The
+/// parser's own tokens, with a [`TokenKind::Word`] for each shell word, are in
+/// [`Parsed::tokens`].
 pub fn lex_module(source: &str) -> (Vec<Token>, Option<ParseError>) {
     let mut lexer = TokenSource::from_source(source, Mode::Module, TextSize::default());
     loop {
@@ -345,6 +351,7 @@ pub fn parse_cells_unchecked(
     let mut tokens = Vec::new();
     let mut errors = Vec::new();
     let mut unsupported_syntax_errors = Vec::new();
+    let mut warnings = Vec::new();
     let mut module_range: Option<TextRange> = None;
 
     while let Some(range) = ranges.next() {
@@ -360,6 +367,7 @@ pub fn parse_cells_unchecked(
             tokens: cell_tokens,
             errors: cell_errors,
             unsupported_syntax_errors: cell_unsupported_syntax_errors,
+            warnings: cell_warnings,
         } = Parser::new_starts_at(cell_source, range.start(), options.clone())
             .parse()
             .try_into_module()
@@ -369,6 +377,7 @@ pub fn parse_cells_unchecked(
         tokens.extend(cell_tokens.iter().copied());
         errors.extend(cell_errors);
         unsupported_syntax_errors.extend(cell_unsupported_syntax_errors);
+        warnings.extend(cell_warnings);
 
         // Each range excludes its trailing `\n` separator (see the doc comment above), leaving a
         // one-byte gap in the token stream. Cover it with a `NonLogicalNewline` so token-based
@@ -394,6 +403,7 @@ pub fn parse_cells_unchecked(
     tokens.shrink_to_fit();
     errors.shrink_to_fit();
     unsupported_syntax_errors.shrink_to_fit();
+    warnings.shrink_to_fit();
 
     Parsed {
         syntax: ModModule {
@@ -404,6 +414,7 @@ pub fn parse_cells_unchecked(
         tokens: Tokens::new(tokens),
         errors,
         unsupported_syntax_errors,
+        warnings,
     }
 }
 
@@ -414,6 +425,7 @@ pub struct Parsed<T> {
     tokens: Tokens,
     errors: Vec<ParseError>,
     unsupported_syntax_errors: Vec<UnsupportedSyntaxError>,
+    warnings: Vec<ParseError>,
 }
 
 impl<T> Parsed<T> {
@@ -437,6 +449,12 @@ impl<T> Parsed<T> {
         &self.unsupported_syntax_errors
     }
 
+    /// Returns a list of the non-fatal errors found during parsing, like invalid regex literals
+    /// with [`PatternSeverity::Warning`].
+    pub fn warnings(&self) -> &[ParseError] {
+        &self.warnings
+    }
+
     /// Consumes the [`Parsed`] output and returns the contained syntax node.
     pub fn into_syntax(self) -> T {
         self.syntax
@@ -523,6 +541,7 @@ impl Parsed<Mod> {
                 tokens: self.tokens,
                 errors: self.errors,
                 unsupported_syntax_errors: self.unsupported_syntax_errors,
+                warnings: self.warnings,
             }),
             Mod::Expression(_) => None,
         }
@@ -543,6 +562,7 @@ impl Parsed<Mod> {
                 tokens: self.tokens,
                 errors: self.errors,
                 unsupported_syntax_errors: self.unsupported_syntax_errors,
+                warnings: self.warnings,
             }),
         }
     }
@@ -0,0 +1,287 @@
+//! The names that a source binds in Python, so that a line starting with one of them isn't
+//! taken for a command when the names defined at runtime are known, see
+//! [`ParseOptions::with_known_names`](crate::ParseOptions::with_known_names).
+//!
+//! The names are collected from the tokens rather than the AST, since the lines to parse as
+//! commands depend on them. Only binding positions count: assignment, `for`, `as`, import,
+//! `global` and `nonlocal` targets, walrus names, and the names and parameters of functions,
+//! classes and lambdas. A name that is only read, like `ls` in `print(ls)`, isn't bound.
+
+use ruff_text_size::{TextRange, TextSize};
+use rustc_hash::FxHashSet;
+
+use crate::Mode;
+use crate::lexer::Lexer;
+use crate::token::TokenKind;
+
+type Token = (TokenKind, TextRange);
+
+/// Returns the names that `source` binds.
+pub(super) fn names_bound_in(source: &str, mode: Mode) -> FxHashSet<&str> {
+    let mut names = FxHashSet::default();
+    let mut lexer = Lexer::new(source, mode, TextSize::default());
+    let mut statement = Vec::new();
+    loop {
+        let kind = lexer.next_token();
+        match kind {
+            TokenKind::Newline | TokenKind::Semi | TokenKind::EndOfFile => {
+                bind_in_expressions(source, &statement, &mut names);
+                bind_in_statement(source, &statement, &mut names);
+                statement.clear();
+                if kind == TokenKind::EndOfFile {
+                    return names;
+                }
+            }
+            TokenKind::Indent | TokenKind::Dedent => {}
+            kind if kind.is_trivia() => {}
+            kind => statement.push((kind, lexer.current_range())),
+        }
+    }
+}
+
+/// Binds the names that can be bound anywhere in a statement, by `:=`, `as`, the `for` of a
+/// comprehension or a lambda.
+fn bind_in_expressions<'a>(source: &'a str, tokens: &[Token], names: &mut FxHashSet<&'a str>) {
+    for (i, &(kind, range)) in tokens.iter().enumerate() {
+        let next = tokens.get(i + 1).map(|&(kind, _)| kind);
+        match kind {
+            _ if is_name(kind) && next == Some(TokenKind::ColonEqual) => {
+                names.insert(&source[range]);
+            }
+            TokenKind::As => {
+                if let Some(&(kind, range)) = tokens.get(i + 1)
+                    && is_name(kind)
+                {
+                    names.insert(&source[range]);
+                }
+            }
+            TokenKind::For => {
+                let targets = &tokens[i + 1..];
+                if let Some(end) = find_top_level(targets, TokenKind::In) {
+                    bind_targets(source, &targets[..end], names);
+                }
+            }
+            TokenKind::Lambda => {
+                let parameters = &tokens[i + 1..];
+                if let Some(end) = find_top_level(parameters, TokenKind::Colon) {
+                    bind_parameters(source, &parameters[..end], names);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Binds the names that the statement itself binds, like the targets of an assignment, the
+/// name of a function or the names of an import.
+fn bind_in_statement<'a>(source: &'a str, tokens: &[Token], names: &mut FxHashSet<&'a str>) {
+    let tokens = match tokens {
+        [(TokenKind::Async, _), rest @ ..] => rest,
+        tokens => tokens,
+    };
+    let Some(&(first, _)) = tokens.first() else {
+        return;
+    };
+    match first {
+        TokenKind::Def | TokenKind::Class => {
+            if let Some(&(kind, range)) = tokens.get(1)
+                && is_name(kind)
+            {
+                names.insert(&source[range]);
+            }
+            if first == TokenKind::Def {
+                // Skips the type parameters of `def f[T](x)`.
+                let mut open = 2;
+                if tokens
+                    .get(open)
+                    .is_some_and(|&(kind, _)| kind == TokenKind::Lsqb)
+                {
+                    open = closing_bracket(tokens, open).map_or(tokens.len(), |close| close + 1);
+                }
+                if tokens
+                    .get(open)
+                    .is_some_and(|&(kind, _)| kind == TokenKind::Lpar)
+                    && let Some(close) = closing_bracket(tokens, open)
+                {
+                    bind_parameters(source, &tokens[open + 1..close], names);
+                }
+            }
+        }
+        TokenKind::Import | TokenKind::From => {
+            let Some(import) = tokens
+                .iter()
+                .position(|&(kind, _)| kind == TokenKind::Import)
+            else {
+                return;
+            };
+            // The names after `as` are bound by `bind_in_expressions`.
+            for alias in split_top_level(&tokens[import + 1..]) {
+                let alias = match alias {
+                    [(TokenKind::Lpar, _), rest @ ..] => rest,
+                    alias => alias,
+                };
+                if let Some(&(kind, range)) = alias.first()
+                    && is_name(kind)
+                    && !alias.iter().any(|&(kind, _)| kind == TokenKind::As)
+                {
+                    names.insert(&source[range]);
+                }
+            }
+        }
+        TokenKind::Global | TokenKind::Nonlocal => {
+            names.extend(
+                tokens
+                    .iter()
+                    .filter(|&&(kind, _)| is_name(kind))
+                    .map(|&(_, range)| &source[range]),
+            );
+        }
+        TokenKind::If
+        | TokenKind::Elif
+        | TokenKind::Else
+        | TokenKind::While
+        | TokenKind::For
+        | TokenKind::With
+        | TokenKind::Try
+        | TokenKind::Except
+        | TokenKind::Finally => {
+            // The body of `if x: y = 1` on the same line.
+            if let Some(colon) = find_top_level(tokens, TokenKind::Colon) {
+                bind_in_statement(source, &tokens[colon + 1..], names);
+            }
+        }
+        _ => bind_assignment(source, tokens, names),
+    }
+}
+
+/// Binds the targets of an assignment like `a, (b, *c) = d = value`, `x: int = 1` or `x += 1`.
+fn bind_assignment<'a>(source: &'a str, tokens: &[Token], names: &mut FxHashSet<&'a str>) {
+    let mut depth = 0u32;
+    let mut start = 0;
+    for (i, &(kind, _)) in tokens.iter().enumerate() {
+        match kind {
+            TokenKind::Lpar | TokenKind::Lsqb | TokenKind::Lbrace => depth += 1,
+            TokenKind::Rpar | TokenKind::Rsqb | TokenKind::Rbrace => {
+                depth = depth.saturating_sub(1);
+            }
+            _ if depth > 0 => {}
+            TokenKind::Equal => {
+                bind_targets(source, &tokens[start..i], names);
+                start = i + 1;
+            }
+            // The annotation of `x: int = 1` and the value of `x += 1` bind nothing.
+            _ if kind == TokenKind::Colon || kind.as_augmented_assign_operator().is_some() => {
+                if start == 0 {
+                    bind_targets(source, &tokens[..i], names);
+                }
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Binds the names of a target list like `a, (b, *c)` or `x.y, z`, unless the tokens can't be
+/// one, like `echo a` in `echo a = b`.
+fn bind_targets<'a>(source: &'a str, tokens: &[Token], names: &mut FxHashSet<&'a str>) {
+    let mut targets = Vec::new();
+    if collect_targets(source, tokens, &mut targets) {
+        names.extend(targets);
+    }
+}
+
+/// Collects the names of a target list, and returns `false` if the tokens aren't one.
+fn collect_targets<'a>(source: &'a str, tokens: &[Token], targets: &mut Vec<&'a str>) -> bool {
+    for target in split_top_level(tokens) {
+        let target = match target {
+            [(TokenKind::Star, _), rest @ ..] => rest,
+            target => target,
+        };
+        match target {
+            // The end of `a, b,`.
+            [] => {}
+            [(kind, range)] if is_name(*kind) => targets.push(&source[*range]),
+            [
+                (TokenKind::Lpar | TokenKind::Lsqb, _),
+                ..,
+                (TokenKind::Rpar | TokenKind::Rsqb, _),
+            ] if closing_bracket(target, 0) == Some(target.len() - 1) => {
+                if !collect_targets(source, &target[1..target.len() - 1], targets) {
+                    return false;
+                }
+            }
+            // An attribute or a subscript, like `a.b` or `a[0]`, binds no name.
+            [
+                (kind, _),
+                (TokenKind::Dot | TokenKind::Lsqb | TokenKind::Lpar, _),
+                ..,
+            ] if is_name(*kind) => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Binds the parameters of a function or a lambda, like `a, b=1, *args, **kwargs`.
+fn bind_parameters<'a>(source: &'a str, tokens: &[Token], names: &mut FxHashSet<&'a str>) {
+    for parameter in split_top_level(tokens) {
+        if let Some(&(kind, range)) = parameter
+            .iter()
+            .find(|&&(kind, _)| !matches!(kind, TokenKind::Star | TokenKind::DoubleStar))
+            && is_name(kind)
+        {
+            names.insert(&source[range]);
+        }
+    }
+}
+
+/// Splits the tokens at the commas outside of brackets.
+fn split_top_level(tokens: &[Token]) -> Vec<&[Token]> {
+    let mut parts = Vec::new();
+    let mut rest = tokens;
+    while let Some(comma) = find_top_level(rest, TokenKind::Comma) {
+        parts.push(&rest[..comma]);
+        rest = &rest[comma + 1..];
+    }
+    parts.push(rest);
+    parts
+}
+
+/// Returns the index of the first token of the kind outside of brackets.
+fn find_top_level(tokens: &[Token], target: TokenKind) -> Option<usize> {
+    let mut depth = 0u32;
+    for (i, &(kind, _)) in tokens.iter().enumerate() {
+        match kind {
+            _ if kind == target && depth == 0 => return Some(i),
+            TokenKind::Lpar | TokenKind::Lsqb | TokenKind::Lbrace => depth += 1,
+            TokenKind::Rpar | TokenKind::Rsqb | TokenKind::Rbrace => {
+                depth = depth.checked_sub(1)?;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the index of the bracket that closes the one at `open`.
+fn closing_bracket(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0u32;
+    for (i, &(kind, _)) in tokens.iter().enumerate().skip(open) {
+        match kind {
+            TokenKind::Lpar | TokenKind::Lsqb | TokenKind::Lbrace => depth += 1,
+            TokenKind::Rpar | TokenKind::Rsqb | TokenKind::Rbrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns `true` for a name, including the soft keywords like `match` or `type`.
+fn is_name(kind: TokenKind) -> bool {
+    kind == TokenKind::Name || kind.is_soft_keyword()
+}
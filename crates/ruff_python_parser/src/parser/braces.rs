@@ -0,0 +1,244 @@
+//! Brace expansion in the arguments of a command, like `cp file.{txt,bak} dir` or
+//! `touch log{1..3}`.
+//!
+//! Arguments are expanded at parse time into a list with an element for each alternative. The
+//! parts of an argument that aren't bare words, like `$HOME` or `'{a,b}'`, are kept as they are
+//! in every alternative, so `{$A,b}/x` becomes `[$A + '/x', 'b/x']`.
+
+use ruff_python_ast::{self as ast, AtomicNodeIndex, Expr, ExprContext};
+use ruff_text_size::{Ranged, TextRange};
+
+use crate::parser::Parser;
+use crate::parser::subproc::{ESCAPED_CHARS, concat_parts, string_literal, unescape_word};
+
+/// The most alternatives an argument is expanded to, so that `{1..1000000}` is left as it is.
+const MAX_ALTERNATIVES: usize = 1024;
+
+/// The alternatives of an argument or a brace group, as the items of each of them.
+type Alternatives<'a> = Vec<Vec<Item<'a>>>;
+
+/// An argument that would expand to more than [`MAX_ALTERNATIVES`] alternatives, which is then
+/// left as it is as a whole.
+struct TooManyAlternatives;
+
+/// A character of a bare word or another part of an argument.
+#[derive(Clone, Copy)]
+enum Item<'a> {
+    Char(char),
+    /// A character escaped with a backslash, which doesn't open, close or split a group.
+    Escaped(char),
+    Part(&'a Expr),
+}
+
+impl Parser<'_> {
+    /// Expands the brace groups of an argument made of the given adjacent parts, if it has any.
+    pub(super) fn expand_braces(&self, first: &Expr, rest: &[Expr]) -> Option<Expr> {
+        let parts = || std::iter::once(first).chain(rest);
+        let is_word = |part: &Expr| match part {
+            Expr::StringLiteral(string) => {
+                unescape_word(&self.source[string.range]) == *string.value.to_str()
+            }
+            _ => false,
+        };
+        if !parts().any(|part| is_word(part) && self.source[part.range()].contains('{')) {
+            return None;
+        }
+
+        let mut items = Vec::new();
+        for part in parts() {
+            match part {
+                Expr::StringLiteral(_) if is_word(part) => {
+                    let mut chars = self.source[part.range()].chars().peekable();
+                    while let Some(c) = chars.next() {
+                        if c == '\\'
+                            && let Some(next) = chars.next_if(|&next| ESCAPED_CHARS.contains(next))
+                        {
+                            items.push(Item::Escaped(next));
+                        } else {
+                            items.push(Item::Char(c));
+                        }
+                    }
+                }
+                _ => items.push(Item::Part(part)),
+            }
+        }
+
+        let range = TextRange::new(first.start(), rest.last().unwrap_or(first).end());
+        let elts = expand(&items)
+            .ok()
+            .flatten()?
+            .iter()
+            .map(|alternative| join(alternative, range))
+            .collect();
+        Some(Expr::List(ast::ExprList {
+            elts,
+            ctx: ExprContext::Load,
+            range,
+            node_index: AtomicNodeIndex::NONE,
+        }))
+    }
+}
+
+/// Expands the first brace group of `items` and the ones in its alternatives and after it.
+///
+/// Returns `None` if there is no brace group.
+fn expand<'a>(items: &[Item<'a>]) -> Result<Option<Alternatives<'a>>, TooManyAlternatives> {
+    for (start, item) in items.iter().enumerate() {
+        if !matches!(item, Item::Char('{')) {
+            continue;
+        }
+        let Some(end) = closing_brace(&items[start + 1..]).map(|end| start + 1 + end) else {
+            continue;
+        };
+        // `{}` and `{a}` are words, but a group inside them can be expanded, e.g. `{x{a,b}}`.
+        let Some(alternatives) = alternatives(&items[start + 1..end])? else {
+            continue;
+        };
+
+        let prefix = &items[..start];
+        let suffixes = expand_all(&items[end + 1..])?;
+        let mut expanded = Vec::new();
+        for alternative in alternatives {
+            for alternative in expand_all(&alternative)? {
+                if expanded.len() + suffixes.len() > MAX_ALTERNATIVES {
+                    return Err(TooManyAlternatives);
+                }
+                for suffix in &suffixes {
+                    expanded.push([prefix, &alternative, suffix].concat());
+                }
+            }
+        }
+        return Ok(Some(expanded));
+    }
+    Ok(None)
+}
+
+/// Like [`expand`], but returns `items` as the only alternative if it has no brace group.
+fn expand_all<'a>(items: &[Item<'a>]) -> Result<Alternatives<'a>, TooManyAlternatives> {
+    Ok(expand(items)?.unwrap_or_else(|| vec![items.to_vec()]))
+}
+
+/// Returns the index of the `}` that closes a group, in the items after its `{`.
+fn closing_brace(items: &[Item]) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, item) in items.iter().enumerate() {
+        match item {
+            Item::Char('{') => depth += 1,
+            Item::Char('}') if depth == 0 => return Some(index),
+            Item::Char('}') => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the alternatives of the items between the braces of a group, which are separated by
+/// commas or are a sequence like `1..5`.
+fn alternatives<'a>(items: &[Item<'a>]) -> Result<Option<Alternatives<'a>>, TooManyAlternatives> {
+    let mut alternatives = vec![Vec::new()];
+    let mut depth = 0usize;
+    for item in items {
+        match item {
+            Item::Char(',') if depth == 0 => {
+                alternatives.push(Vec::new());
+                continue;
+            }
+            Item::Char('{') => depth += 1,
+            Item::Char('}') => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if let Some(alternative) = alternatives.last_mut() {
+            alternative.push(*item);
+        }
+    }
+    if alternatives.len() > 1 {
+        return Ok(Some(alternatives));
+    }
+
+    let Some(text) = items
+        .iter()
+        .map(|item| match item {
+            Item::Char(c) => Some(*c),
+            Item::Escaped(_) | Item::Part(_) => None,
+        })
+        .collect::<Option<String>>()
+    else {
+        return Ok(None);
+    };
+    let words = sequence(&text)?;
+    Ok(words.map(|words| {
+        words
+            .iter()
+            .map(|word| word.chars().map(Item::Char).collect())
+            .collect()
+    }))
+}
+
+/// Returns the words of a sequence like `1..5`, `05..10` or `a..e`.
+fn sequence(text: &str) -> Result<Option<Vec<String>>, TooManyAlternatives> {
+    let Some((first, last)) = text.split_once("..") else {
+        return Ok(None);
+    };
+    if let (Ok(start), Ok(end)) = (first.parse::<i64>(), last.parse::<i64>()) {
+        if start.abs_diff(end) >= MAX_ALTERNATIVES as u64 {
+            return Err(TooManyAlternatives);
+        }
+        // `05..10` pads the numbers with zeros to the same width.
+        let is_padded = |number: &str| {
+            let digits = number.trim_start_matches('-');
+            digits.len() > 1 && digits.starts_with('0')
+        };
+        let width = if is_padded(first) || is_padded(last) {
+            first.len().max(last.len())
+        } else {
+            0
+        };
+        let mut numbers: Vec<_> = (start.min(end)..=start.max(end))
+            .map(|number| format!("{number:0width$}"))
+            .collect();
+        if start > end {
+            numbers.reverse();
+        }
+        return Ok(Some(numbers));
+    }
+
+    let (&[start], &[end]) = (first.as_bytes(), last.as_bytes()) else {
+        return Ok(None);
+    };
+    if !start.is_ascii_alphabetic() || !end.is_ascii_alphabetic() {
+        return Ok(None);
+    }
+    let mut letters: Vec<_> = (start.min(end)..=start.max(end))
+        .map(|letter| char::from(letter).to_string())
+        .collect();
+    if start > end {
+        letters.reverse();
+    }
+    Ok(Some(letters))
+}
+
+/// Turns the items of an alternative back into an argument.
+fn join(items: &[Item], range: TextRange) -> Expr {
+    let mut parts = Vec::new();
+    let mut word = String::new();
+    for item in items {
+        match item {
+            Item::Char(c) | Item::Escaped(c) => word.push(*c),
+            Item::Part(part) => {
+                if !word.is_empty() {
+                    parts.push(string_literal(range, std::mem::take(&mut word)));
+                }
+                parts.push((*part).clone());
+            }
+        }
+    }
+    if !word.is_empty() || parts.is_empty() {
+        parts.push(string_literal(range, word));
+    }
+
+    let mut parts = parts.into_iter();
+    match parts.next() {
+        Some(first) => concat_parts(first, parts.collect()),
+        None => string_literal(range, String::new()),
+    }
+}
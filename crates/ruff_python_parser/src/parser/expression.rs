@@ -73,6 +73,7 @@ pub(super) const EXPR_SET: TokenSet = TokenSet::new([
     TokenKind::DollarLSqb,
     TokenKind::DollarLBrace,
     TokenKind::AtDollarLParen,
+    TokenKind::AtDot,
     TokenKind::BackTick,
     TokenKind::At,
 ])
@@ -593,7 +594,10 @@ impl<'src> Parser<'src> {
         self.parse_identifier_with_context(ExpressionContext::default())
     }
 
-    fn parse_identifier_with_context(&mut self, context: ExpressionContext) -> ast::Identifier {
+    pub(super) fn parse_identifier_with_context(
+        &mut self,
+        context: ExpressionContext,
+    ) -> ast::Identifier {
         let range = self.current_token_range();
 
         if self.at(TokenKind::Name) {
@@ -732,6 +736,16 @@ impl<'src> Parser<'src> {
             TokenKind::Name => Expr::Name(self.parse_name(context)),
             TokenKind::Dollar => self.parse_env_name(),
             TokenKind::DollarLBrace => self.parse_env_expr(),
+            TokenKind::AtDot => self.parse_interface_attr(context),
+            TokenKind::At
+                if matches!(
+                    self.peek2(),
+                    (TokenKind::Name, TokenKind::String | TokenKind::FStringStart)
+                ) =>
+            {
+                self.bump(TokenKind::At);
+                self.parse_pattern_invoke().into()
+            }
             TokenKind::IpyEscapeCommand => {
                 Expr::IpyEscapeCommand(self.parse_ipython_escape_command_expression())
             }
@@ -748,8 +762,13 @@ impl<'src> Parser<'src> {
 
             kind => {
                 if let Some((method, closing)) = SUBPROC_TOKENS.get(&kind) {
+                    if kind == TokenKind::DollarLParen {
+                        self.check_shell_arithmetic();
+                    }
+                    // The nesting outside of the `$(`, to recover to if it isn't closed.
+                    let nesting = self.tokens.nesting().saturating_sub(1);
                     self.bump_any(); // skip the `$(`
-                    self.parse_subprocs((*method).to_string(), *closing)
+                    self.parse_subprocs((*method).to_string(), *closing, nesting)
                 } else if kind.is_keyword() {
                     Expr::Name(self.parse_name(context))
                 } else {
@@ -25,15 +25,19 @@ use rustc_hash::FxHashSet;
 use thin_vec::ThinVec;
 use unicode_normalization::UnicodeNormalization;
 
-pub use crate::parser::options::ParseOptions;
+pub use crate::parser::options::{ParseOptions, PatternSeverity};
 
+mod bindings;
+mod braces;
 mod expression;
 mod helpers;
 mod options;
 mod pattern;
+mod pattern_literals;
 mod progress;
 mod recovery;
 mod scratch_buffer;
+mod shell;
 mod statement;
 mod subproc;
 
@@ -78,15 +82,30 @@ pub(crate) struct Parser<'src> {
     /// Stores all the syntax errors found during the parsing.
     errors: Vec<ParseError>,
 
-    /// Ranges of subproc commands parsed in subproc mode.
-    subproc_ranges: Vec<TextRange>,
+    /// The indentation of the `with!` suite being parsed, which ends at the first line that is
+    /// indented less, even if a bracket was left open in it.
+    with_macro_indent: usize,
+
+    /// The indentation of the line where the innermost subprocess starts, if the subprocess is
+    /// never closed. Its arguments then only continue on the lines inside brackets that are
+    /// indented more, to recover at a line that doesn't belong to it.
+    proc_indent: Option<usize>,
 
     /// Stores non-fatal syntax errors found during parsing, such as version-related errors.
     unsupported_syntax_errors: Vec<UnsupportedSyntaxError>,
 
+    /// Stores the errors that are reported as warnings, like invalid regex literals with
+    /// [`PatternSeverity::Warning`].
+    warnings: Vec<ParseError>,
+
     /// Options for how the code will be parsed.
     options: ParseOptions,
 
+    /// The names that the source binds, like assignment targets and parameters, which are
+    /// Python names like the [`ParseOptions::with_known_names`] ones. Only collected when those
+    /// are given.
+    bound_names: FxHashSet<&'src str>,
+
     /// The ID of the current token. This is used to track the progress of the parser
     /// to avoid infinite loops when the parser is stuck.
     current_token_id: TokenId,
@@ -140,13 +159,21 @@ impl<'src> Parser<'src> {
         let tokens = TokenSource::from_source(source, options.mode, start_offset);
         let depth_remaining = options.max_recursion_depth;
         let max_nesting_depth = u32::from(options.max_recursion_depth.saturating_sub(2));
+        let bound_names = if options.known_names.is_some() {
+            bindings::names_bound_in(source, options.mode)
+        } else {
+            FxHashSet::default()
+        };
 
         Parser {
             options,
+            bound_names,
             source,
             errors: Vec::new(),
-            subproc_ranges: Vec::new(),
+            with_macro_indent: 0,
+            proc_indent: None,
             unsupported_syntax_errors: Vec::new(),
+            warnings: Vec::new(),
             tokens,
             name_interner: NameInterner::default(),
             name_buffer: String::new(),
@@ -275,20 +302,6 @@ impl<'src> Parser<'src> {
         let parse_errors = self.errors;
         let (tokens, lex_errors) = self.tokens.finish();
 
-        let lex_errors: Vec<_> = lex_errors
-            .into_iter()
-            .filter(|err| {
-                if let crate::LexicalErrorType::OtherError(msg) = err.error() {
-                    if msg.as_ref() == "Invalid decimal integer literal"
-                        && self.subproc_ranges.iter().any(|r| r.contains_range(err.location()))
-                    {
-                        return false;
-                    }
-                }
-                true
-            })
-            .collect();
-
         // Fast path for when there are no lex errors.
         // There's no fast path for when there are no parse errors because a lex error
         // always results in a parse error.
@@ -298,6 +311,7 @@ impl<'src> Parser<'src> {
                 tokens: Tokens::new(tokens),
                 errors: parse_errors,
                 unsupported_syntax_errors: self.unsupported_syntax_errors,
+                warnings: self.warnings,
             };
         }
 
@@ -330,6 +344,7 @@ impl<'src> Parser<'src> {
             tokens: Tokens::new(tokens),
             errors: merged,
             unsupported_syntax_errors: self.unsupported_syntax_errors,
+            warnings: self.warnings,
         }
     }
 
@@ -800,6 +815,24 @@ impl<'src> Parser<'src> {
         self.recovery_context = saved_context;
     }
 
+    /// Parses a construct that isn't a list in the given recovery context, so that the lists
+    /// nested in it can recover at its terminators.
+    fn with_recovery_context<T>(
+        &mut self,
+        recovery_context_kind: RecoveryContextKind,
+        parse: impl FnOnce(&mut Parser<'src>) -> T,
+    ) -> T {
+        let saved_context = self.recovery_context;
+        self.recovery_context = self
+            .recovery_context
+            .union(RecoveryContext::from_kind(recovery_context_kind));
+
+        let result = parse(self);
+
+        self.recovery_context = saved_context;
+        result
+    }
+
     /// Parses a comma separated list of elements into a vector where each element
     /// is parsed using the given `parse_element` function.
     fn parse_comma_separated_list_into_vec<T>(
@@ -970,6 +1003,7 @@ impl<'src> Parser<'src> {
             tokens: self.tokens.checkpoint(),
             errors_position: self.errors.len(),
             unsupported_syntax_errors_position: self.unsupported_syntax_errors.len(),
+            warnings_position: self.warnings.len(),
             current_token_id: self.current_token_id,
             prev_token_end: self.prev_token_end,
             recovery_context: self.recovery_context,
@@ -982,6 +1016,7 @@ impl<'src> Parser<'src> {
             tokens,
             errors_position,
             unsupported_syntax_errors_position,
+            warnings_position,
             current_token_id,
             prev_token_end,
             recovery_context,
@@ -991,6 +1026,7 @@ impl<'src> Parser<'src> {
         self.errors.truncate(errors_position);
         self.unsupported_syntax_errors
             .truncate(unsupported_syntax_errors_position);
+        self.warnings.truncate(warnings_position);
         self.current_token_id = current_token_id;
         self.prev_token_end = prev_token_end;
         self.recovery_context = recovery_context;
@@ -1021,6 +1057,7 @@ struct ParserCheckpoint {
     tokens: TokenSourceCheckpoint,
     errors_position: usize,
     unsupported_syntax_errors_position: usize,
+    warnings_position: usize,
     current_token_id: TokenId,
     prev_token_end: TextSize,
     recovery_context: RecoveryContext,
@@ -1161,6 +1198,43 @@ impl Parenthesized {
     }
 }
 
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum SubprocKind {
+    /// A `$(...)`, `!(...)` or `@$(...)` subprocess.
+    Parenthesized,
+    /// A `$[...]` or `![...]` subprocess.
+    Bracketed,
+    /// A bare command, ended by the end of the line.
+    Bare,
+}
+
+impl SubprocKind {
+    const fn from_closing(closing: TokenKind) -> Self {
+        match closing {
+            TokenKind::Rpar => SubprocKind::Parenthesized,
+            TokenKind::Rsqb => SubprocKind::Bracketed,
+            _ => SubprocKind::Bare,
+        }
+    }
+
+    /// Returns the token that ends the subprocess.
+    const fn closing(self) -> TokenKind {
+        match self {
+            SubprocKind::Parenthesized => TokenKind::Rpar,
+            SubprocKind::Bracketed => TokenKind::Rsqb,
+            SubprocKind::Bare => TokenKind::Newline,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum MacroArgumentsKind {
+    /// The arguments can span several lines, e.g., `f!(x,\n y)`.
+    Multiline,
+    /// The closing `)` is missing, so the arguments end with the line.
+    SingleLine,
+}
+
 #[derive(Copy, Clone, Debug)]
 enum ListTerminatorKind {
     /// The current token terminates the list.
@@ -1241,6 +1315,18 @@ enum RecoveryContextKind {
 
     /// When parsing a list of f-string or t-string elements which are either literal elements, expressions, or interpolations.
     InterpolatedStringElements(InterpolatedStringElementsKind),
+
+    /// When parsing the arguments and redirects of a command in a subprocess, e.g., `$(ls -l | grep x)`
+    SubprocArguments(SubprocKind),
+
+    /// When parsing the expression of an environment variable lookup, e.g., `${'HO' + 'ME'}`
+    EnvExpression,
+
+    /// When parsing the raw arguments of a call macro, e.g., `f!(x + 1, y)`
+    MacroArguments(MacroArgumentsKind),
+
+    /// When parsing the verbatim suite of a `with!` statement
+    WithMacroSuite,
 }
 
 impl RecoveryContextKind {
@@ -1263,6 +1349,7 @@ impl RecoveryContextKind {
                 | RecoveryContextKind::TypeParams
                 | RecoveryContextKind::DeleteTargets
                 | RecoveryContextKind::ImportFromAsNames(Parenthesized::Yes)
+                | RecoveryContextKind::MacroArguments(_)
         )
     }
 
@@ -1465,6 +1552,31 @@ impl RecoveryContextKind {
                         .then_some(ListTerminatorKind::ErrorRecovery)
                 }
             }
+            // A command continues on the next line while its bracket is open. If the bracket is
+            // never closed, the line break ends it unless the line is indented more than the one
+            // it starts on, and the command reports the missing bracket itself before recovering
+            // at the start of the next logical line.
+            RecoveryContextKind::SubprocArguments(kind) => (p.at(kind.closing())
+                || p.at(TokenKind::Newline)
+                || (kind == SubprocKind::Bare && p.at(TokenKind::Semi))
+                || p.at_cmd_separator()
+                || p.at_proc_line_break())
+            .then_some(ListTerminatorKind::Regular),
+            RecoveryContextKind::EnvExpression => {
+                (p.at(TokenKind::Rbrace) || p.at(TokenKind::Newline) || p.at_line_break())
+                    .then_some(ListTerminatorKind::Regular)
+            }
+            // The arguments of a call macro are only cut at a line break once it's known that the
+            // closing `)` is missing.
+            RecoveryContextKind::MacroArguments(kind) => (p.at(TokenKind::Rpar)
+                || p.at(TokenKind::Newline)
+                || (kind == MacroArgumentsKind::SingleLine && p.at_line_break()))
+            .then_some(ListTerminatorKind::Regular),
+            // A line that is indented less than the suite ends it, even inside open brackets.
+            RecoveryContextKind::WithMacroSuite => (p.at(TokenKind::Dedent)
+                || (p.at_line_break()
+                    && p.line_indent(p.current_token_range().start()) < p.with_macro_indent))
+                .then_some(ListTerminatorKind::Regular),
         }
     }
 
@@ -1514,6 +1626,11 @@ impl RecoveryContextKind {
                         || p.current_token_kind() == TokenKind::Lbrace
                 }
             },
+            // The words of commands, the raw macro arguments and suites can contain any token.
+            RecoveryContextKind::SubprocArguments(_)
+            | RecoveryContextKind::MacroArguments(_)
+            | RecoveryContextKind::WithMacroSuite => !self.is_list_terminator(p),
+            RecoveryContextKind::EnvExpression => p.at_expr(),
         }
     }
 
@@ -1611,15 +1728,27 @@ impl RecoveryContextKind {
                     ))
                 }
             },
+            RecoveryContextKind::SubprocArguments(kind) => {
+                ParseErrorType::ExpectedCommandArgument(kind.closing())
+            }
+            RecoveryContextKind::EnvExpression => {
+                ParseErrorType::InvalidEnvironmentExpression(p.current_token_kind())
+            }
+            RecoveryContextKind::MacroArguments(_) => {
+                ParseErrorType::InvalidMacroArgument(p.current_token_kind())
+            }
+            RecoveryContextKind::WithMacroSuite => {
+                ParseErrorType::InvalidWithMacroSuite(p.current_token_kind())
+            }
         }
     }
 }
 
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
-struct RecoveryContext(u32);
+struct RecoveryContext(u64);
 
 bitflags! {
-    impl RecoveryContext: u32 {
+    impl RecoveryContext: u64 {
         const MODULE_STATEMENTS = 1 << 0;
         const BLOCK_STATEMENTS = 1 << 1;
         const ELIF = 1 << 2;
@@ -1652,6 +1781,13 @@ bitflags! {
         const T_STRING_ELEMENTS = 1 << 29;
         const F_STRING_ELEMENTS_IN_FORMAT_SPEC = 1 << 30;
         const T_STRING_ELEMENTS_IN_FORMAT_SPEC = 1 << 31;
+        const SUBPROC_ARGUMENTS_PARENTHESIZED = 1 << 32;
+        const SUBPROC_ARGUMENTS_BRACKETED = 1 << 33;
+        const SUBPROC_ARGUMENTS_BARE = 1 << 34;
+        const ENV_EXPRESSION = 1 << 35;
+        const MACRO_ARGUMENTS_MULTILINE = 1 << 36;
+        const MACRO_ARGUMENTS_SINGLE_LINE = 1 << 37;
+        const WITH_MACRO_SUITE = 1 << 38;
     }
 }
 
@@ -1719,6 +1855,17 @@ impl RecoveryContext {
                     RecoveryContext::T_STRING_ELEMENTS_IN_FORMAT_SPEC
                 }
             },
+            RecoveryContextKind::SubprocArguments(kind) => match kind {
+                SubprocKind::Parenthesized => RecoveryContext::SUBPROC_ARGUMENTS_PARENTHESIZED,
+                SubprocKind::Bracketed => RecoveryContext::SUBPROC_ARGUMENTS_BRACKETED,
+                SubprocKind::Bare => RecoveryContext::SUBPROC_ARGUMENTS_BARE,
+            },
+            RecoveryContextKind::EnvExpression => RecoveryContext::ENV_EXPRESSION,
+            RecoveryContextKind::MacroArguments(kind) => match kind {
+                MacroArgumentsKind::Multiline => RecoveryContext::MACRO_ARGUMENTS_MULTILINE,
+                MacroArgumentsKind::SingleLine => RecoveryContext::MACRO_ARGUMENTS_SINGLE_LINE,
+            },
+            RecoveryContextKind::WithMacroSuite => RecoveryContext::WITH_MACRO_SUITE,
         }
     }
 
@@ -1801,6 +1948,23 @@ impl RecoveryContext {
                     InterpolatedStringElementsKind::FormatSpec(InterpolatedStringKind::TString),
                 )
             }
+            RecoveryContext::SUBPROC_ARGUMENTS_PARENTHESIZED => {
+                RecoveryContextKind::SubprocArguments(SubprocKind::Parenthesized)
+            }
+            RecoveryContext::SUBPROC_ARGUMENTS_BRACKETED => {
+                RecoveryContextKind::SubprocArguments(SubprocKind::Bracketed)
+            }
+            RecoveryContext::SUBPROC_ARGUMENTS_BARE => {
+                RecoveryContextKind::SubprocArguments(SubprocKind::Bare)
+            }
+            RecoveryContext::ENV_EXPRESSION => RecoveryContextKind::EnvExpression,
+            RecoveryContext::MACRO_ARGUMENTS_MULTILINE => {
+                RecoveryContextKind::MacroArguments(MacroArgumentsKind::Multiline)
+            }
+            RecoveryContext::MACRO_ARGUMENTS_SINGLE_LINE => {
+                RecoveryContextKind::MacroArguments(MacroArgumentsKind::SingleLine)
+            }
+            RecoveryContext::WITH_MACRO_SUITE => RecoveryContextKind::WithMacroSuite,
             _ => return None,
         })
     }
@@ -1,4 +1,7 @@
+use std::sync::Arc;
+
 use ruff_python_ast::{PySourceType, PythonVersion};
+use rustc_hash::FxHashSet;
 
 use crate::{AsMode, Mode};
 
@@ -45,6 +48,22 @@ pub struct ParseOptions {
     /// nested expression / statement / pattern nodes are on the parser's call
     /// stack. Defaults to [`DEFAULT_MAX_RECURSION_DEPTH`].
     pub(crate) max_recursion_depth: u16,
+    /// The names defined in the Python scope of the source, if they are known. A line that
+    /// starts with another name, like `ls -l`, is then parsed as a command.
+    pub(crate) known_names: Option<Arc<FxHashSet<String>>>,
+    /// How invalid regex and glob literals are reported.
+    pub(crate) pattern_severity: PatternSeverity,
+}
+
+/// How the parser reports the errors in the static parts of regex and glob literals, like
+/// `` `(a` `` or `g"[a-z"`, which would otherwise only fail when the pattern is used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PatternSeverity {
+    /// Report them in [`crate::Parsed::warnings`], which doesn't make the syntax invalid.
+    #[default]
+    Warning,
+    /// Report them as syntax errors.
+    Error,
 }
 
 impl ParseOptions {
@@ -68,6 +87,21 @@ impl ParseOptions {
     pub fn max_recursion_depth(&self) -> u16 {
         self.max_recursion_depth
     }
+
+    /// Set the names defined in the Python scope of the source, like the `ctx` of xonsh's
+    /// `Execer`, to parse lines that start with other names as commands.
+    #[must_use]
+    pub fn with_known_names<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.known_names = Some(Arc::new(names.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Set whether invalid regex and glob literals are reported as warnings or as errors.
+    #[must_use]
+    pub fn with_pattern_severity(mut self, severity: PatternSeverity) -> Self {
+        self.pattern_severity = severity;
+        self
+    }
 }
 
 impl From<Mode> for ParseOptions {
@@ -76,6 +110,8 @@ impl From<Mode> for ParseOptions {
             mode,
             target_version: PythonVersion::default(),
             max_recursion_depth: DEFAULT_MAX_RECURSION_DEPTH,
+            known_names: None,
+            pattern_severity: PatternSeverity::default(),
         }
     }
 }
@@ -86,6 +122,8 @@ impl From<PySourceType> for ParseOptions {
             mode: source_type.as_mode(),
             target_version: PythonVersion::default(),
             max_recursion_depth: DEFAULT_MAX_RECURSION_DEPTH,
+            known_names: None,
+            pattern_severity: PatternSeverity::default(),
         }
     }
 }
@@ -0,0 +1,384 @@
+//! Checks of regex and glob literals, like `` `(a|b)+` `` or `g"*.[ch]"`, which xonsh only
+//! compiles when the pattern is used.
+//!
+//! The static parts of a literal are checked with the escapes of its string resolved, and each
+//! error points at the characters of the source that it is about. An interpolation of an f-string
+//! stands for a single character, so `` f`({x})` `` is a balanced group.
+
+use ruff_python_ast::{self as ast, Expr, StringFlags};
+use ruff_text_size::{Ranged, TextRange, TextSize};
+
+use crate::error::PatternErrorType;
+use crate::parser::{Parser, PatternSeverity};
+use crate::{ParseError, ParseErrorType};
+
+/// The character that an interpolation stands for.
+const INTERPOLATION: char = '\u{fffc}';
+
+/// A character of a pattern, along with the range of the source it comes from.
+type PatternChar = (char, TextRange);
+
+impl Parser<'_> {
+    /// Reports the first error in the static parts of a regex, or of a glob if `glob` is set.
+    pub(super) fn check_pattern_literal(&mut self, expr: &Expr, glob: bool) {
+        let Some(chars) = self.pattern_chars(expr) else {
+            return;
+        };
+        let (error, range) = if glob {
+            let Err((kind, range)) = check_glob(&chars) else {
+                return;
+            };
+            (ParseErrorType::InvalidGlob(kind), range)
+        } else {
+            let Err((kind, range)) = check_regex(&chars) else {
+                return;
+            };
+            (ParseErrorType::InvalidRegex(kind), range)
+        };
+        match self.options.pattern_severity {
+            PatternSeverity::Warning => self.warnings.push(ParseError {
+                error,
+                location: range,
+            }),
+            PatternSeverity::Error => self.add_error(error, range),
+        }
+    }
+
+    /// Returns the characters of a string or f-string, or `None` if one of its parts is unclosed.
+    fn pattern_chars(&self, expr: &Expr) -> Option<Vec<PatternChar>> {
+        let mut chars = Vec::new();
+        match expr {
+            Expr::StringLiteral(string) => {
+                for part in string.value.iter() {
+                    self.push_literal_chars(part, &mut chars)?;
+                }
+            }
+            Expr::FString(string) => {
+                for part in string.value.iter() {
+                    match part {
+                        ast::FStringPart::Literal(literal) => {
+                            self.push_literal_chars(literal, &mut chars)?;
+                        }
+                        ast::FStringPart::FString(fstring) => {
+                            let raw = is_raw_prefix(&self.source[fstring.range()]);
+                            for element in fstring.elements.iter() {
+                                match element {
+                                    ast::InterpolatedStringElement::Literal(literal) => {
+                                        push_chars(
+                                            &self.source[literal.range()],
+                                            literal.start(),
+                                            raw,
+                                            true,
+                                            &mut chars,
+                                        );
+                                    }
+                                    ast::InterpolatedStringElement::Interpolation(element) => {
+                                        chars.push((INTERPOLATION, element.range()));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            _ => return None,
+        }
+        Some(chars)
+    }
+
+    fn push_literal_chars(
+        &self,
+        literal: &ast::StringLiteral,
+        chars: &mut Vec<PatternChar>,
+    ) -> Option<()> {
+        let text = &self.source[literal.range()];
+        let quoted = text.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        let quote_len = if literal.flags.is_triple_quoted() {
+            3
+        } else {
+            1
+        };
+        if quoted.len() < 2 * quote_len || !quoted.ends_with(&quoted[..quote_len]) {
+            return None;
+        }
+        let content = &quoted[quote_len..quoted.len() - quote_len];
+        let start = literal.start() + text_size(text.len() - quoted.len() + quote_len);
+        push_chars(content, start, is_raw_prefix(text), false, chars);
+        Some(())
+    }
+}
+
+fn is_raw_prefix(text: &str) -> bool {
+    text.chars()
+        .take_while(char::is_ascii_alphabetic)
+        .any(|c| matches!(c, 'r' | 'R'))
+}
+
+/// Pushes the characters of the source of a string, with its escapes resolved, or its doubled
+/// braces if it is the literal part of an f-string.
+fn push_chars(
+    source: &str,
+    start: TextSize,
+    raw: bool,
+    interpolated: bool,
+    chars: &mut Vec<PatternChar>,
+) {
+    let mut index = 0;
+    while let Some(c) = source[index..].chars().next() {
+        let rest = &source[index + c.len_utf8()..];
+        let (value, len) = match c {
+            '\\' if !raw => match escape(rest) {
+                Some((value, len)) => (value, 1 + len),
+                // Unknown escapes are kept as they are.
+                None => (Some(c), 1),
+            },
+            '{' | '}' if interpolated && rest.starts_with(c) => (Some(c), 2),
+            _ => (Some(c), c.len_utf8()),
+        };
+        if let Some(value) = value {
+            chars.push((
+                value,
+                TextRange::at(start + text_size(index), text_size(len)),
+            ));
+        }
+        index += len;
+    }
+}
+
+/// Returns the character of the escape at the start of `rest`, which follows a backslash, and the
+/// length of the escape. The character is `None` for a line continuation.
+fn escape(rest: &str) -> Option<(Option<char>, usize)> {
+    let next = rest.chars().next()?;
+    let code = |max: usize, radix: u32| {
+        let digits = rest[1..]
+            .chars()
+            .take(max)
+            .take_while(|c| c.is_digit(radix))
+            .count();
+        let value = u32::from_str_radix(&rest[1..=digits], radix)
+            .ok()
+            .and_then(char::from_u32)
+            .unwrap_or(INTERPOLATION);
+        (Some(value), 1 + digits)
+    };
+    Some(match next {
+        '\n' => (None, 1),
+        '\r' => (None, if rest[1..].starts_with('\n') { 2 } else { 1 }),
+        '\\' | '\'' | '"' => (Some(next), 1),
+        'a' => (Some('\x07'), 1),
+        'b' => (Some('\x08'), 1),
+        'f' => (Some('\x0c'), 1),
+        'n' => (Some('\n'), 1),
+        'r' => (Some('\r'), 1),
+        't' => (Some('\t'), 1),
+        'v' => (Some('\x0b'), 1),
+        '0'..='7' => {
+            let digits = 1 + rest[1..]
+                .chars()
+                .take(2)
+                .take_while(|c| c.is_digit(8))
+                .count();
+            let value = u32::from_str_radix(&rest[..digits], 8)
+                .ok()
+                .and_then(char::from_u32)
+                .unwrap_or(INTERPOLATION);
+            (Some(value), digits)
+        }
+        'x' => code(2, 16),
+        'u' => code(4, 16),
+        'U' => code(8, 16),
+        // The named character stands for any other one.
+        'N' => match rest[1..].strip_prefix('{').and_then(|name| name.find('}')) {
+            Some(end) => (Some(INTERPOLATION), end + 3),
+            None => (Some(INTERPOLATION), 1),
+        },
+        _ => return None,
+    })
+}
+
+fn text_size(len: usize) -> TextSize {
+    TextSize::try_from(len).expect("strings are smaller than 4GB")
+}
+
+/// Checks a regex like Python's `re` module, for the errors that it reports when compiling it.
+fn check_regex(chars: &[PatternChar]) -> Result<(), (PatternErrorType, TextRange)> {
+    let mut groups = Vec::new();
+    let mut index = 0;
+    while let Some(&(c, range)) = chars.get(index) {
+        index += 1;
+        match c {
+            '\\' => index = check_escape(chars, index - 1, false)?.0,
+            '[' => index = check_regex_class(chars, index - 1)?,
+            '(' if chars.get(index).map(|c| c.0) == Some('?')
+                && chars.get(index + 1).map(|c| c.0) == Some('#') =>
+            {
+                // A comment, which ends at the first `)`.
+                let end = chars[index..]
+                    .iter()
+                    .position(|c| c.0 == ')')
+                    .ok_or((PatternErrorType::UnterminatedGroup, range))?;
+                index += end + 1;
+            }
+            '(' => groups.push(range),
+            ')' => {
+                groups
+                    .pop()
+                    .ok_or((PatternErrorType::UnbalancedParenthesis, range))?;
+            }
+            _ => {}
+        }
+    }
+    match groups.pop() {
+        Some(range) => Err((PatternErrorType::UnterminatedGroup, range)),
+        None => Ok(()),
+    }
+}
+
+/// Checks the character class of a regex that starts at `start`, and returns the index after it.
+fn check_regex_class(
+    chars: &[PatternChar],
+    start: usize,
+) -> Result<usize, (PatternErrorType, TextRange)> {
+    // Returns the index after the item of the class at `index`, and the character it matches.
+    let item = |index: usize| match chars[index].0 {
+        '\\' => check_escape(chars, index, true),
+        c => Ok((index + 1, Some(c))),
+    };
+    let mut index = start + 1;
+    if chars.get(index).is_some_and(|c| c.0 == '^') {
+        index += 1;
+    }
+    // A `]` right after the `[` is part of the class.
+    let mut first = true;
+    while let Some(&(c, range)) = chars.get(index) {
+        if c == ']' && !first {
+            return Ok(index + 1);
+        }
+        first = false;
+        let (next, value) = item(index)?;
+        index = next;
+        if chars.get(index).is_some_and(|c| c.0 == '-')
+            && chars.get(index + 1).is_some_and(|c| c.0 != ']')
+        {
+            // A range like `a-z`.
+            let (after, end) = item(index + 1)?;
+            if let (Some(value), Some(end)) = (value, end)
+                && value != INTERPOLATION
+                && end != INTERPOLATION
+                && value > end
+            {
+                let range = TextRange::new(range.start(), chars[after - 1].1.end());
+                return Err((PatternErrorType::BadCharacterRange(value, end), range));
+            }
+            index = after;
+        }
+    }
+    Err((PatternErrorType::UnterminatedCharacterSet, chars[start].1))
+}
+
+/// Checks the escape of a regex at `start`, and returns the index after it along with the
+/// character that it matches, unless it is a class like `\d`.
+fn check_escape(
+    chars: &[PatternChar],
+    start: usize,
+    in_class: bool,
+) -> Result<(usize, Option<char>), (PatternErrorType, TextRange)> {
+    let backslash = chars[start].1;
+    let Some(&(c, _)) = chars.get(start + 1) else {
+        return Err((PatternErrorType::BadEscape("\\".to_string()), backslash));
+    };
+    let bad_escape = |end: usize| {
+        let escape = chars[start..end].iter().map(|c| c.0).collect();
+        let range = TextRange::new(backslash.start(), chars[end - 1].1.end());
+        (PatternErrorType::BadEscape(escape), range)
+    };
+    let hex_digits = match c {
+        'x' => 2,
+        'u' => 4,
+        'U' => 8,
+        _ => 0,
+    };
+    if hex_digits > 0 {
+        let end = start + 2 + hex_digits;
+        let digits = &chars[start + 2..end.min(chars.len())];
+        let valid = digits
+            .iter()
+            .take_while(|c| c.0.is_ascii_hexdigit())
+            .count();
+        if valid < hex_digits {
+            return Err(bad_escape(start + 2 + valid));
+        }
+        let value = digits
+            .iter()
+            .try_fold(0, |value: u32, c| Some(value * 16 + c.0.to_digit(16)?))
+            .and_then(char::from_u32);
+        return Ok((end, value));
+    }
+    let value = match c {
+        'd' | 'D' | 's' | 'S' | 'w' | 'W' => None,
+        'A' | 'B' | 'Z' if !in_class => None,
+        'b' if !in_class => None,
+        'b' => Some('\x08'),
+        'a' => Some('\x07'),
+        'f' => Some('\x0c'),
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        'v' => Some('\x0b'),
+        'N' => {
+            // A named character like `\N{DIGIT ONE}`.
+            let name_end = chars[start + 2..]
+                .iter()
+                .position(|c| c.0 == '}')
+                .filter(|_| chars.get(start + 2).is_some_and(|c| c.0 == '{'));
+            return match name_end {
+                Some(name_end) => Ok((start + 3 + name_end, None)),
+                None => Err(bad_escape(start + 2)),
+            };
+        }
+        // Octal escapes in classes, group references outside of them.
+        '0'..='9' => None,
+        c if c.is_ascii_alphabetic() => return Err(bad_escape(start + 2)),
+        c => Some(c),
+    };
+    Ok((start + 2, value))
+}
+
+/// Checks a glob like Python's `fnmatch` module, which matches an unterminated `[` as it is and
+/// nothing with an empty range like `[z-a]`.
+fn check_glob(chars: &[PatternChar]) -> Result<(), (PatternErrorType, TextRange)> {
+    let mut index = 0;
+    while let Some(&(c, range)) = chars.get(index) {
+        index += 1;
+        if c != '[' {
+            continue;
+        }
+        if chars.get(index).is_some_and(|c| c.0 == '!') {
+            index += 1;
+        }
+        // A `]` right after the `[` is part of the class.
+        let class_start = index;
+        let end = chars
+            .iter()
+            .enumerate()
+            .skip(class_start + 1)
+            .find(|(_, c)| c.0 == ']')
+            .map(|(end, _)| end)
+            .ok_or((PatternErrorType::UnterminatedCharacterSet, range))?;
+        let mut item = class_start;
+        while item < end {
+            if item + 2 < end && chars[item + 1].0 == '-' {
+                let (low, high) = (chars[item].0, chars[item + 2].0);
+                if low != INTERPOLATION && high != INTERPOLATION && low > high {
+                    let range = TextRange::new(chars[item].1.start(), chars[item + 2].1.end());
+                    return Err((PatternErrorType::BadCharacterRange(low, high), range));
+                }
+                item += 3;
+            } else {
+                item += 1;
+            }
+        }
+        index = end + 1;
+    }
+    Ok(())
+}
@@ -0,0 +1,269 @@
+//! Detection of bash constructs written in xonsh code. The ones without a xonsh equivalent are
+//! reported along with the xonsh code that replaces them.
+
+use ruff_text_size::{TextLen, TextRange, TextSize};
+
+use crate::error::{ShellSyntaxError, ShellSyntaxKind};
+use crate::parser::Parser;
+use crate::{ParseErrorType, TokenKind};
+
+impl<'src> Parser<'src> {
+    fn add_shell_syntax_error(&mut self, kind: ShellSyntaxKind, range: TextRange, fix: String) {
+        self.add_error(
+            ParseErrorType::ShellSyntax(ShellSyntaxError { kind, fix }),
+            range,
+        );
+    }
+
+    /// Returns the start of the current token and the rest of its line, without the trailing
+    /// whitespace.
+    fn rest_of_line(&self) -> (TextSize, &'src str) {
+        let start = self.current_token_range().start();
+        let rest = &self.source[start.to_usize()..];
+        let end = rest.find(['\n', '\r']).unwrap_or(rest.len());
+        (start, rest[..end].trim_end())
+    }
+
+    /// Returns `true` if the line starts with `NAME=value` words followed by a command, like
+    /// `FOO=bar make test`, rather than with an assignment.
+    pub(super) fn at_env_prefixed_command(&self) -> bool {
+        if !self.at(TokenKind::Name) || self.peek() != TokenKind::Equal {
+            return false;
+        }
+        let mut rest = &self.source[self.current_token_range().start().to_usize()..];
+        loop {
+            let Some((_, value)) = split_env_name(rest) else {
+                return false;
+            };
+            let value_len = shell_word_len(value);
+            if is_keyword(&value[..value_len]) {
+                return false;
+            }
+            let after_value = &value[value_len..];
+            let next = after_value.trim_start_matches([' ', '\t']);
+            if next.len() == after_value.len() {
+                // The line ends after the value.
+                return false;
+            }
+            if split_env_name(next).is_some() {
+                rest = next;
+                continue;
+            }
+            let word_len = next
+                .find(|c: char| c != '_' && !c.is_alphanumeric())
+                .unwrap_or(next.len());
+            let word = &next[..word_len];
+            return is_identifier(word) && !is_keyword(word);
+        }
+    }
+
+    /// Reports `export NAME=value`, which is `$NAME = 'value'` in xonsh.
+    pub(super) fn check_shell_export(&mut self) {
+        let (start, line) = self.rest_of_line();
+        let Some(assignment) = line
+            .strip_prefix("export")
+            .filter(|rest| rest.starts_with([' ', '\t']))
+        else {
+            return;
+        };
+        let assignment = assignment.trim_start();
+        let Some((name, value)) = split_assignment(assignment) else {
+            return;
+        };
+        let end = line.len() - assignment.len() + name.len() + 1 + value.len();
+        self.add_shell_syntax_error(
+            ShellSyntaxKind::Export,
+            TextRange::at(start, line[..end].text_len()),
+            format!("${name} = {}", quote_word(value)),
+        );
+    }
+
+    /// Reports a bash test like `[ -f x ]; then` as the condition of an `if`, `elif` or `while`
+    /// statement, which is `![test -f x]:` in xonsh.
+    pub(super) fn check_shell_condition(&mut self) {
+        if !self.at(TokenKind::Lsqb) {
+            return;
+        }
+        let (start, line) = self.rest_of_line();
+        let Some(condition) = line
+            .strip_suffix("then")
+            .or_else(|| line.strip_suffix("do"))
+            .and_then(|rest| rest.trim_end().strip_suffix(';'))
+            .map(str::trim_end)
+        else {
+            return;
+        };
+        let Some(test) = condition
+            .strip_prefix("[[")
+            .and_then(|rest| rest.strip_suffix("]]"))
+            .or_else(|| {
+                condition
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.strip_suffix(']'))
+            })
+        else {
+            return;
+        };
+        self.add_shell_syntax_error(
+            ShellSyntaxKind::TestCondition,
+            TextRange::at(start, line.text_len()),
+            format!("![test {}]:", test.trim()),
+        );
+    }
+
+    /// Reports the words of a bash loop like `for f in *.txt; do`, which is
+    /// `for f in g'*.txt':` in xonsh.
+    pub(super) fn check_shell_for_iter(&mut self) {
+        let (start, line) = self.rest_of_line();
+        let Some(items) = line
+            .strip_suffix("do")
+            .and_then(|rest| rest.trim_end().strip_suffix(';'))
+        else {
+            return;
+        };
+        let words = split_words(items);
+        if words.is_empty()
+            || words
+                .iter()
+                .any(|word| word.contains(['$', '`', '(', ')', '{', '}']))
+        {
+            return;
+        }
+        let iter = match &words[..] {
+            [word] if !is_quoted(word) && word.contains(['*', '?', '[']) => format!("g'{word}'"),
+            words => {
+                let words: Vec<_> = words.iter().map(|word| quote_word(word)).collect();
+                format!("[{}]", words.join(", "))
+            }
+        };
+        self.add_shell_syntax_error(
+            ShellSyntaxKind::ForDo,
+            TextRange::at(start, line.text_len()),
+            format!("{iter}:"),
+        );
+    }
+
+    /// Reports bash arithmetic like `$((1 + 2))` at the current `$(` token, which is a Python
+    /// expression in xonsh.
+    pub(super) fn check_shell_arithmetic(&mut self) {
+        let (start, line) = self.rest_of_line();
+        let Some(rest) = line.strip_prefix("$((") else {
+            return;
+        };
+        let mut depth = 0;
+        for (index, c) in rest.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth > 0 => depth -= 1,
+                ')' => {
+                    if rest[index + 1..].starts_with(')') {
+                        let len = line[..3 + index + 2].text_len();
+                        self.add_shell_syntax_error(
+                            ShellSyntaxKind::ArithmeticExpansion,
+                            TextRange::at(start, len),
+                            format!("({})", rest[..index].trim()),
+                        );
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Reports a bash default value like `${NAME:-default}` in the given `${...}` range, which is
+    /// `${...}.get('NAME', 'default')` in xonsh.
+    pub(super) fn check_shell_default_value(&mut self, range: TextRange) {
+        let Some(inner) = self.source[range]
+            .strip_prefix("${")
+            .and_then(|rest| rest.strip_suffix('}'))
+        else {
+            return;
+        };
+        let Some((name, default)) = inner.split_once(":-") else {
+            return;
+        };
+        if !is_identifier(name) {
+            return;
+        }
+        self.add_shell_syntax_error(
+            ShellSyntaxKind::DefaultValue,
+            range,
+            format!("${{...}}.get('{name}', {})", quote_word(default.trim())),
+        );
+    }
+}
+
+/// Splits `NAME=word` at the start of `text` into the name and the word.
+fn split_assignment(text: &str) -> Option<(&str, &str)> {
+    let (name, rest) = split_env_name(text)?;
+    let word = &rest[..shell_word_len(rest)];
+    (!word.is_empty()).then_some((name, word))
+}
+
+/// Splits `NAME=` at the start of `text` into the name and the text after the `=`.
+fn split_env_name(text: &str) -> Option<(&str, &str)> {
+    let (name, rest) = text.split_once('=')?;
+    (is_identifier(name) && !rest.starts_with('=')).then_some((name, rest))
+}
+
+/// Splits `text` into bash words, keeping quoted words together.
+fn split_words(mut text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    loop {
+        text = text.trim_start();
+        if text.is_empty() {
+            return words;
+        }
+        let len = shell_word_len(text).max(1);
+        words.push(&text[..len]);
+        text = &text[len..];
+    }
+}
+
+/// Returns the length of the bash word at the start of `text`, which includes any quoted or
+/// bracketed text like in `$(ls -l)`.
+fn shell_word_len(text: &str) -> usize {
+    let mut quote = None;
+    let mut depth = 0usize;
+    for (index, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if matches!(c, '(' | '[' | '{') => depth += 1,
+            None if matches!(c, ')' | ']' | '}') => depth = depth.saturating_sub(1),
+            None if depth == 0 && (c.is_whitespace() || c == ';') => return index,
+            None => {}
+        }
+    }
+    text.len()
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c == '_' || c.is_alphabetic())
+        && chars.all(|c| c == '_' || c.is_alphanumeric())
+}
+
+/// Returns `true` for the Python keywords that can continue an expression or start one.
+fn is_keyword(word: &str) -> bool {
+    matches!(
+        word,
+        "and" | "or" | "not" | "in" | "is" | "if" | "else" | "for" | "lambda" | "await" | "yield"
+    )
+}
+
+fn is_quoted(word: &str) -> bool {
+    word.starts_with(['\'', '"'])
+}
+
+/// Turns a bash word into a Python expression, quoting it unless it is a string or an
+/// environment variable already.
+fn quote_word(word: &str) -> String {
+    if is_quoted(word) || word.starts_with('$') {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
@@ -92,7 +92,7 @@ impl<'src> Parser<'src> {
     /// Returns `true` if the current token is the start of a simple, compound or expression
     /// statement.
     pub(super) fn at_stmt(&self) -> bool {
-        self.at_ts(STMTS_SET) || self.at_soft_keyword()
+        self.at_ts(STMTS_SET) || self.at_soft_keyword() || self.at_path_command()
     }
 
     /// Checks if the parser is currently positioned at the start of a type parameter.
@@ -163,7 +163,10 @@ impl<'src> Parser<'src> {
 
         if !has_eaten_newline {
             if !has_eaten_semicolon && self.at_simple_stmt() {
-                return self.parse_bare_proc();
+                let stmt = self.parse_bare_proc();
+                self.eat(TokenKind::Semi);
+                self.eat(TokenKind::Newline);
+                return stmt;
                 // // test_err simple_stmts_on_same_line
                 // // a b
                 // // a + b c + d
@@ -287,14 +290,19 @@ impl<'src> Parser<'src> {
             TokenKind::IpyEscapeCommand => {
                 Stmt::IpyEscapeCommand(self.parse_ipython_escape_command_statement())
             }
+            TokenKind::Name if self.at_env_prefixed_command() => self.parse_bare_proc(),
+            _ if self.at_path_command() => self.parse_bare_proc(),
+            TokenKind::Name if self.at_command_with_shell_words() => self.parse_bare_proc(),
             TokenKind::Name
                 if matches!(
                     self.peek(),
                     TokenKind::Name | TokenKind::DoublePipe | TokenKind::DoubleAmp
                 ) =>
             {
+                self.check_shell_export();
                 self.parse_bare_proc()
             }
+            TokenKind::Name if self.at_unknown_name_command() => self.parse_bare_proc(),
             token => {
                 if token == TokenKind::Lazy {
                     let start = self.node_start();
@@ -1447,6 +1455,7 @@ impl<'src> Parser<'src> {
 
         // test_err if_stmt_missing_test
         // if : ...
+        self.check_shell_condition();
         let test = self.parse_named_expression_or_higher(ExpressionContext::default());
 
         // test_err if_stmt_missing_colon
@@ -1505,6 +1514,7 @@ impl<'src> Parser<'src> {
             //     pass
             // elif yield x:
             //     pass
+            self.check_shell_condition();
             Some(
                 self.parse_named_expression_or_higher(ExpressionContext::default())
                     .expr,
@@ -1904,6 +1914,7 @@ impl<'src> Parser<'src> {
         // for x in *a and b: ...
         // for x in yield a: ...
         // for target in x := 1: ...
+        self.check_shell_for_iter();
         let iter = self.parse_expression_list(ExpressionContext::starred_bitwise_or());
 
         // test_ok for_iter_unpack_py39
@@ -1971,6 +1982,7 @@ impl<'src> Parser<'src> {
         // while yield x: ...
         // while a, b: ...
         // while a := 1, b: ...
+        self.check_shell_condition();
         let test = self.parse_named_expression_or_higher(ExpressionContext::default());
 
         // test_err while_stmt_missing_colon
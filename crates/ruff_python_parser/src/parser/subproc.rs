@@ -4,46 +4,138 @@ use ruff_python_ast::name::Name;
 use ruff_python_ast::{
     self as ast, AtomicNodeIndex, DictItem, Expr, ExprContext, ExprDict, ExprTuple,
 };
-use ruff_text_size::{Ranged, TextRange, TextSize};
+use ruff_text_size::{Ranged, TextLen, TextRange, TextSize};
 
-use crate::ParseErrorType;
 use crate::builders::ExprWrap;
+use crate::lexer::Lexer;
 use crate::token::TokenFlags;
+use crate::{Mode, ParseErrorType};
 
 use crate::parser::expression::ExpressionContext;
 use crate::{
-    parser::{Parser, ParserProgress},
+    parser::{MacroArgumentsKind, Parser, ParserProgress, RecoveryContextKind, SubprocKind},
     token::TokenKind,
 };
 
+/// The stream names that can be redirected with `>`, like in `e>o` or `out>> log.txt`.
+const REDIR_NAMES: &[&str] = &["o", "out", "e", "err", "a", "all"];
+
+/// The modifiers that can come before a command, like in `$(@json curl ...)`.
+const SPEC_MODIFIERS: &[&str] = &["json", "lines", "path", "paths", "unthread", "error_raise"];
+
+/// The characters that a backslash escapes in a shell word, like in `My\ Documents` or `\$HOME`.
+/// A backslash before any other character is kept, so that paths like `C:\Users` still work.
+pub(super) const ESCAPED_CHARS: &str = " \t\\'\"`#$@!|&;<>()[]{},";
+
 impl Parser<'_> {
     /// Parses a subprocess expression.
     /// This includes various forms of subprocess capture like `$(...)`, `$[...]`, `!(...)`, and `![...]`.
-    pub(super) fn parse_subprocs(&mut self, method: impl Into<Name>, closing: TokenKind) -> Expr {
+    ///
+    /// `nesting` is the bracket nesting of the lexer outside of the subprocess, which is restored
+    /// when its closing bracket is missing.
+    pub(super) fn parse_subprocs(
+        &mut self,
+        method: impl Into<Name>,
+        closing: TokenKind,
+        nesting: u32,
+    ) -> Expr {
+        let method = method.into();
         let start = self.node_start();
 
-        let mut cmd = self
-            .xonsh_attr("cmd")
-            .call(self.parse_cmd_group(closing), self.node_range(start));
-        while self.at(TokenKind::Vbar) {
-            let pipe_start = self.node_start();
+        let indent = (!self.proc_is_closed(start, closing)).then(|| self.line_indent(start));
+        let outer_indent = std::mem::replace(&mut self.proc_indent, indent);
+        let expr = self.parse_cmd_chain(ast::BoolOp::Or, &method, closing, nesting);
+        self.proc_indent = outer_indent;
+        expr
+    }
+
+    /// Parses pipelines chained with `&&`/`and` or `||`/`or` into a boolean operation, e.g.
+    /// `$[make && make install || echo failed]`. `&&` binds tighter than `||`, like `and` and
+    /// `or` in Python mode.
+    fn parse_cmd_chain(
+        &mut self,
+        op: ast::BoolOp,
+        method: &Name,
+        closing: TokenKind,
+        nesting: u32,
+    ) -> Expr {
+        let start = self.node_start();
+        let mut values = Vec::new();
+        loop {
+            values.push(match op {
+                ast::BoolOp::Or => self.parse_cmd_chain(ast::BoolOp::And, method, closing, nesting),
+                ast::BoolOp::And => self.parse_pipeline(method, closing, nesting),
+            });
+            if self.current_token_kind().as_bool_operator() != Some(op) {
+                break;
+            }
             self.bump_any();
-            cmd = cmd
-                .attr("pipe", self.node_range(pipe_start))
-                .call(self.parse_cmd_group(closing), self.node_range(pipe_start));
         }
-        let end = self.node_end();
-        self.subproc_ranges.push(TextRange::new(start, end));
 
+        if values.len() == 1 {
+            return values.remove(0);
+        }
+        Expr::BoolOp(ast::ExprBoolOp {
+            op,
+            values,
+            range: self.node_range(start),
+            node_index: AtomicNodeIndex::NONE,
+        })
+    }
+
+    /// Parses commands joined by pipes and calls `method` on the pipeline.
+    ///
+    /// A pipeline followed by `&&` or `||` is only tested for success, so it is run with `hide`
+    /// when the output isn't captured and with `obj` otherwise.
+    fn parse_pipeline(&mut self, method: &Name, closing: TokenKind, nesting: u32) -> Expr {
+        let start = self.node_start();
+        let cmd = self.parse_cmd_pipeline(closing, nesting);
+
+        let method = if self.current_token_kind().as_bool_operator().is_none() {
+            method.clone()
+        } else if matches!(method.as_str(), "hide" | "run") {
+            Name::new_static("hide")
+        } else {
+            Name::new_static("obj")
+        };
         cmd.attr(method, self.node_range(start))
             .call_empty(self.node_range(start))
             .into()
     }
 
+    /// Parses commands joined by pipes into a `__xonsh__.cmd(...).pipe(...)` call chain.
+    fn parse_cmd_pipeline(&mut self, closing: TokenKind, nesting: u32) -> ExprWrap {
+        let start = self.node_start();
+
+        let mut cmd = self.xonsh_attr("cmd").call(
+            self.parse_cmd_group(closing, nesting),
+            self.node_range(start),
+        );
+        while self.at(TokenKind::Vbar) || self.at_stderr_pipe() {
+            let pipe_start = self.node_start();
+            let stream = self.parse_pipe();
+            let mut group = self.parse_cmd_group(closing, nesting);
+            if let Some(stream) = stream {
+                let mut keywords = group.keywords.into_vec();
+                keywords.push(ast::Keyword {
+                    arg: Some(ExprWrap::identifier("stream", stream.range())),
+                    range: stream.range(),
+                    value: stream,
+                    node_index: AtomicNodeIndex::NONE,
+                });
+                group.keywords = keywords.into_boxed_slice();
+            }
+            cmd = cmd
+                .attr("pipe", self.node_range(pipe_start))
+                .call(group, self.node_range(pipe_start));
+        }
+        cmd
+    }
+
     /// Parses a subprocess expression like `ls tmp-dir` with ![]
     pub(super) fn parse_bare_proc(&mut self) -> ast::Stmt {
         let start = self.node_start();
-        let expr = self.parse_subprocs("hide", TokenKind::Newline);
+        let expr = self.parse_subprocs("hide", TokenKind::Newline, self.tokens.nesting());
         ast::Stmt::Expr(ast::StmtExpr {
             range: self.node_range(start),
             value: Box::new(expr),
@@ -51,66 +143,226 @@ impl Parser<'_> {
         })
     }
 
-    fn parse_cmd_group(&mut self, closing: TokenKind) -> ast::Arguments {
-        const REDIR_NAMES: &[&str] = &["o", "out", "e", "err", "a", "all"];
+    /// Returns `true` at a command that starts with a path like `./build.sh`, `../configure`,
+    /// `/usr/bin/env` or `~/bin/tool`, which can't start a Python statement.
+    pub(super) fn at_path_command(&self) -> bool {
+        if !matches!(
+            self.current_token_kind(),
+            TokenKind::Dot | TokenKind::Slash | TokenKind::Tilde
+        ) {
+            return false;
+        }
+        let rest = &self.source[self.current_token_range().start().to_usize()..];
+        ["./", "../", "/", "~/"]
+            .iter()
+            .any(|prefix| rest.starts_with(prefix))
+    }
+
+    /// Returns `true` at a command like `cd ..`, `ls /tmp/` or `./configure --prefix=/usr`,
+    /// whose second word is a flag, a path or dots that don't make the line a Python statement.
+    ///
+    /// The line is parsed as Python first, so that valid Python like `ls -l` or `a /b` keeps
+    /// its meaning.
+    pub(super) fn at_command_with_shell_words(&mut self) -> bool {
+        let name_end = self.current_token_range().end();
+        let next = self.peek();
+        if !matches!(
+            next,
+            TokenKind::Minus | TokenKind::Dot | TokenKind::Slash | TokenKind::Tilde
+        ) {
+            return false;
+        }
+        // The word is separated from the name, unlike in `a.b` or `a-b`, and a flag or a path
+        // starts right after its `-` or `/`, unlike in `a - b`.
+        let rest = &self.source[name_end.to_usize()..];
+        let word = trim_blanks(rest);
+        if word.len() == rest.len() {
+            return false;
+        }
+        if matches!(next, TokenKind::Minus | TokenKind::Slash)
+            && word[1..].starts_with(|c: char| c.is_whitespace())
+        {
+            return false;
+        }
+
+        !self.python_expression_end().is_some_and(|kind| {
+            matches!(
+                kind,
+                TokenKind::Newline | TokenKind::Semi | TokenKind::EndOfFile
+            ) || is_assignment(kind)
+        })
+    }
+
+    /// Returns `true` at a line like `ls -l` whose first name isn't defined in Python, when the
+    /// defined names are known, and which doesn't use the name like Python code, e.g. `x = 1`
+    /// or `f(x)`.
+    ///
+    /// Reading an undefined name fails in Python, so a line that only reads it, like `ls -l`,
+    /// is a command even though it parses as Python, while one that assigns it is Python.
+    pub(super) fn at_unknown_name_command(&mut self) -> bool {
+        let range = self.current_token_range();
+        let Some(names) = &self.options.known_names else {
+            return false;
+        };
+        let name = &self.source[range];
+        if names.contains(name) || self.bound_names.contains(name) {
+            return false;
+        }
+        // `f(x)`, `a[0]` and `a.b` are Python, while `ls (x)`, `ls [a-z]*` and `ls .` aren't.
+        let next = self.peek();
+        let is_adjacent = !self.source[range.end().to_usize()..].starts_with([' ', '\t']);
+        if is_adjacent && matches!(next, TokenKind::Lpar | TokenKind::Lsqb | TokenKind::Dot) {
+            return false;
+        }
+        !self.python_expression_end().is_some_and(is_assignment)
+    }
+
+    /// Parses the line as a Python expression list and rewinds, returning the kind of the token
+    /// after the expressions if they parsed without errors.
+    fn python_expression_end(&mut self) -> Option<TokenKind> {
+        let checkpoint = self.checkpoint();
+        let errors = self.errors.len();
+        self.parse_expression_list(ExpressionContext::yield_or_starred_bitwise_or());
+        let end = (self.errors.len() == errors).then(|| self.current_token_kind());
+        self.rewind(checkpoint);
+        end
+    }
+
+    /// Parses a process substitution like `<(sort a)` or `>(tee log)` into a
+    /// `__xonsh__.process_substitution('<', pipeline)` call. The runtime starts the pipeline
+    /// with its output or input connected to a pipe and passes the path of the pipe, e.g.
+    /// `/dev/fd/63`, to the command.
+    fn parse_process_substitution(&mut self) -> Expr {
+        let start = self.node_start();
+        let mode = self.to_string_literal(TextRange::at(start, '<'.text_len()));
+        // The nesting outside of the `<(`, to recover to if it isn't closed.
+        let nesting = self.tokens.nesting().saturating_sub(1);
+        self.bump_any(); // skip the `<(` or `>(`
+
+        let pipeline = self.parse_cmd_pipeline(TokenKind::Rpar, nesting);
+        while self.current_token_kind().as_bool_operator().is_some() {
+            self.add_error(
+                ParseErrorType::ChainInProcessSubstitution(self.current_token_kind()),
+                self.current_token_range(),
+            );
+            self.bump_any();
+            self.parse_cmd_pipeline(TokenKind::Rpar, nesting);
+        }
+
+        let range = self.node_range(start);
+        self.xonsh_attr("process_substitution")
+            .call0(vec![mode, pipeline.into()], range)
+            .into()
+    }
+
+    /// Parses a `|`, or the `|&` and `e|` pipes that also or only pipe the standard error of the
+    /// command, which are lowered to a `stream='all'` or `stream='err'` keyword of the next one.
+    fn parse_pipe(&mut self) -> Option<Expr> {
+        let start = self.node_start();
+        if self.at_stderr_pipe() {
+            self.bump(TokenKind::Name);
+            self.bump(TokenKind::Vbar);
+            return Some(string_literal(self.node_range(start), "err".to_string()));
+        }
+        self.bump(TokenKind::Vbar);
+        if self.at(TokenKind::Amper) && self.node_start() == self.prev_token_end {
+            self.bump(TokenKind::Amper);
+            return Some(string_literal(self.node_range(start), "all".to_string()));
+        }
+        None
+    }
+
+    fn parse_cmd_group(&mut self, closing: TokenKind, nesting: u32) -> ast::Arguments {
+        let kind = SubprocKind::from_closing(closing);
         let start = self.node_start();
         let mut cmds = Vec::new();
         let mut keywords = Vec::new();
         let mut redirects = Vec::new();
+        let mut env = Vec::new();
+        let mut modifiers = Vec::new();
         let mut progress = ParserProgress::default();
 
-        loop {
-            match self.current_token_kind() {
-                tk if tk == closing => {
-                    self.bump_any();
-                    break;
+        self.parse_list(RecoveryContextKind::SubprocArguments(kind), |p| {
+            p.tokens.re_lex_process_substitution();
+            match p.current_token_kind() {
+                TokenKind::Name if cmds.is_empty() && p.at_env_assignment() => {
+                    let result = p.parse_env_assignment(&mut progress, closing);
+                    env.push(result);
                 }
-                TokenKind::Vbar => break,
-                TokenKind::Int | TokenKind::Amper if matches!(self.peek(), TokenKind::Greater) => {
-                    let result = self.parse_redirection1(closing);
-                    redirects.push(result);
-                }
-                TokenKind::Name
-                    if matches!(self.peek(), TokenKind::Greater)
-                        && REDIR_NAMES.contains(&&self.source[self.current_token_range()]) =>
-                {
-                    let result = self.parse_redirection1(closing);
-                    redirects.push(result);
+                TokenKind::At if p.at_spec_modifier(cmds.is_empty()) => {
+                    if let Some(modifier) = p.parse_spec_modifier(cmds.is_empty()) {
+                        modifiers.push(modifier);
+                    }
                 }
-                TokenKind::RightShift | TokenKind::Greater | TokenKind::Less => {
-                    let result = self.parse_redirection(None, closing);
+                _ if p.at_redirect() => {
+                    let result = p.parse_redirect(&mut progress, closing);
                     redirects.push(result);
                 }
-                TokenKind::Amper if self.peek() == closing => {
+                TokenKind::Amper
+                    if p.peek() == closing
+                        || (kind == SubprocKind::Bare && p.peek() == TokenKind::Semi) =>
+                {
                     keywords.push(ast::Keyword {
-                        arg: Some(self.to_identifier("bg")),
-                        value: self.literal_true(),
-                        range: self.current_token_range(),
+                        arg: Some(p.to_identifier("bg")),
+                        value: p.literal_true(),
+                        range: p.current_token_range(),
                         node_index: AtomicNodeIndex::NONE,
                     });
-                    self.bump_any(); // skip `&`
-                    self.bump(closing); // skip `)`
-                    break;
+                    p.bump_any(); // skip `&`
                 }
-                _ => cmds.push(self.parse_proc_arg(&mut progress, closing)),
+                TokenKind::Amper => {
+                    p.add_error(ParseErrorType::BackgroundNotAtEnd, p.current_token_range());
+                    p.bump_any(); // skip `&`
+                }
+                _ => cmds.push(p.parse_proc_arg(&mut progress, closing)),
+            }
+        });
+
+        // A command that stops at a line break left a bracket open, unless the line starts with
+        // its closing bracket.
+        if !self.at(closing)
+            && self.at_proc_line_break()
+            && self.recover_at_line_break(nesting)
+            && kind == SubprocKind::Bare
+        {
+            self.add_error(
+                ParseErrorType::UnclosedSubprocess(closing),
+                self.current_token_range(),
+            );
+        }
+        if !self.at_cmd_separator() {
+            if kind == SubprocKind::Bare {
+                // The `;` or newline that ends a bare command is left to end its statement, so
+                // that `cd /tmp; x = 1` goes on with the next one.
+                if !matches!(
+                    self.current_token_kind(),
+                    TokenKind::Newline | TokenKind::Semi | TokenKind::EndOfFile
+                ) {
+                    self.expect(closing);
+                }
+            } else if !self.eat(closing) {
+                self.add_error(
+                    ParseErrorType::UnclosedSubprocess(closing),
+                    self.current_token_range(),
+                );
             }
         }
 
-        if let Some(first) = redirects.first() {
-            if let Some(last) = redirects.last() {
-                let range = TextRange::new(first.range().start(), last.range().end());
-                let expr = Expr::from(ExprDict {
+        keywords.extend(self.dict_keyword("env", env, start));
+        keywords.extend(self.dict_keyword("redirects", redirects, start));
+        if let (Some(first), Some(last)) = (modifiers.first(), modifiers.last()) {
+            let range = TextRange::new(first.start(), last.end());
+            keywords.push(ast::Keyword {
+                arg: Some(self.to_identifier("modifiers")),
+                value: Expr::List(ast::ExprList {
+                    elts: modifiers,
+                    ctx: ExprContext::Load,
                     range,
-                    items: redirects,
                     node_index: AtomicNodeIndex::NONE,
-                });
-                keywords.push(ast::Keyword {
-                    arg: Some(self.to_identifier("redirects")),
-                    value: expr,
-                    range: self.node_range(start),
-                    node_index: AtomicNodeIndex::NONE,
-                });
-            }
+                }),
+                range,
+                node_index: AtomicNodeIndex::NONE,
+            });
         }
 
         ast::Arguments {
@@ -121,41 +373,121 @@ impl Parser<'_> {
         }
     }
 
+    /// Creates the `name={...}` keyword of a command from its items, if there are any.
+    fn dict_keyword(
+        &self,
+        name: &str,
+        items: Vec<DictItem>,
+        start: TextSize,
+    ) -> Option<ast::Keyword> {
+        let range = TextRange::new(items.first()?.range().start(), items.last()?.range().end());
+        let expr = Expr::from(ExprDict {
+            range,
+            items,
+            node_index: AtomicNodeIndex::NONE,
+        });
+        Some(ast::Keyword {
+            arg: Some(self.to_identifier(name)),
+            value: expr,
+            range: self.node_range(start),
+            node_index: AtomicNodeIndex::NONE,
+        })
+    }
+
+    /// Returns `true` at a `NAME=` word.
+    fn at_env_assignment(&self) -> bool {
+        self.peek() == TokenKind::Equal
+            && self.source[self.current_token_range().end().to_usize()..].starts_with('=')
+    }
+
+    /// Returns `true` at a modifier like `@json`. After the start of a command, only the known
+    /// modifiers are taken as misplaced ones, and other `@name` words are invalid interpolations.
+    fn at_spec_modifier(&mut self, before_command: bool) -> bool {
+        let (name, next) = self.peek2();
+        let rest = &self.source[self.current_token_range().end().to_usize()..];
+        // `@func"..."` is an interpolation, which `parse_decorator_or_interpolation` handles.
+        if name != TokenKind::Name
+            || matches!(next, TokenKind::String | TokenKind::FStringStart)
+            || !rest.starts_with(is_name_char)
+        {
+            return false;
+        }
+        let len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+        before_command || SPEC_MODIFIERS.contains(&&rest[..len])
+    }
+
+    /// Parses a modifier like `@json` into its name, reporting unknown and misplaced ones.
+    fn parse_spec_modifier(&mut self, before_command: bool) -> Option<Expr> {
+        let start = self.node_start();
+        self.bump(TokenKind::At);
+        let name_range = self.current_token_range();
+        self.bump(TokenKind::Name);
+        let name = self.source[name_range].to_string();
+        let range = self.node_range(start);
+        if !SPEC_MODIFIERS.contains(&name.as_str()) {
+            self.add_error(ParseErrorType::UnknownSpecModifier(name), range);
+            None
+        } else if !before_command {
+            self.add_error(ParseErrorType::MisplacedSpecModifier(name), range);
+            None
+        } else {
+            Some(string_literal(range, name))
+        }
+    }
+
+    /// Parses a `NAME=value` word before a command, which sets an environment variable for it.
+    fn parse_env_assignment(
+        &mut self,
+        progress: &mut ParserProgress,
+        closing: TokenKind,
+    ) -> DictItem {
+        let key = Some(self.to_string_literal(self.current_token_range()));
+        self.bump(TokenKind::Name);
+        let value_start = self.current_token_range().end();
+        self.bump(TokenKind::Equal);
+
+        let tk = self.current_token_kind();
+        let value = if self.node_start() != value_start
+            || tk == closing
+            || matches!(tk, TokenKind::Vbar | TokenKind::EndOfFile)
+            || tk.is_any_newline()
+        {
+            // An empty value, e.g. `FOO= cmd`
+            self.to_string_literal(TextRange::empty(value_start))
+        } else {
+            self.parse_proc_arg(progress, closing)
+        };
+        DictItem { key, value }
+    }
+
     /// Parses arguments in a subprocess expression, joining adjacent parts without whitespace.
     fn parse_proc_arg(&mut self, progress: &mut ParserProgress, closing: TokenKind) -> Expr {
-        let mut parts = vec![self.parse_proc_arg_part(progress, closing)];
+        let first = self.parse_proc_arg_part(progress, closing);
+        let start = first.range().start();
+        let mut end = first.range().end();
+        let mut rest = Vec::new();
 
-        while self.node_start() == parts.last().unwrap().range().end()
-            && !matches!(self.current_token_kind(), tk if tk == closing || tk == TokenKind::Vbar || tk.is_any_newline() || tk.is_proc_op() || tk.is_macro())
+        while self.node_start() == end
+            && !matches!(self.current_token_kind(), tk if tk == closing || tk == TokenKind::Vbar || tk == TokenKind::EndOfFile || tk.is_any_newline() || tk.is_proc_op() || tk.is_macro())
         {
-            parts.push(self.parse_proc_arg_part(progress, closing));
+            let part = self.parse_proc_arg_part(progress, closing);
+            end = part.range().end();
+            rest.push(part);
         }
 
-        if parts.len() == 1 {
-            parts.pop().unwrap()
+        if let Some(expanded) = self.expand_braces(&first, &rest) {
+            expanded
+        } else if rest.is_empty() {
+            first
+        } else if let Some(value) = std::iter::once(&first)
+            .chain(&rest)
+            .map(|part| Some(part.as_string_literal_expr()?.value.to_str()))
+            .collect::<Option<String>>()
+        {
+            // Quoted and bare parts like `"foo"'bar'baz` are one string.
+            string_literal(TextRange::new(start, end), value)
         } else {
-            let all_strings = parts.iter().all(|p| matches!(p, Expr::StringLiteral(_)));
-            let start = parts.first().unwrap().range().start();
-            let end = parts.last().unwrap().range().end();
-            let range = TextRange::new(start, end);
-
-            if all_strings {
-                self.to_string_literal(range)
-            } else {
-                let mut iter = parts.into_iter();
-                let mut expr = iter.next().unwrap();
-                for next in iter {
-                    let r = TextRange::new(expr.range().start(), next.range().end());
-                    expr = Expr::BinOp(ast::ExprBinOp {
-                        left: Box::new(expr),
-                        op: ast::Operator::Add,
-                        right: Box::new(next),
-                        range: r,
-                        node_index: AtomicNodeIndex::NONE,
-                    });
-                }
-                expr
-            }
+            concat_parts(first, rest)
         }
     }
 
@@ -166,12 +498,22 @@ impl Parser<'_> {
         match kind {
             TokenKind::At => self.parse_decorator_or_interpolation(),
             tk if tk.is_macro() => self.parse_proc_macro(closing),
+            TokenKind::LessLpar | TokenKind::GreaterLpar => self.parse_process_substitution(),
+            TokenKind::AtDot => {
+                let start = self.node_start();
+                let expr = self.parse_interface_attr(ExpressionContext::default());
+                self.parse_postfix_expression(expr, start, ExpressionContext::default())
+            }
             TokenKind::String
             | TokenKind::FStringStart
             | TokenKind::TStringStart
             | TokenKind::Lpar
             | TokenKind::Dollar
+            | TokenKind::DollarLBrace
             | TokenKind::DollarLParen
+            | TokenKind::DollarLSqb
+            | TokenKind::BangLParen
+            | TokenKind::BangLSqb
             | TokenKind::AtDollarLParen => self.parse_atom(ExpressionContext::default()).expr,
             tk if tk.is_proc_op() => {
                 let range = self.current_token_range();
@@ -181,48 +523,125 @@ impl Parser<'_> {
             _ => self.parse_proc_single(closing),
         }
     }
+
+    /// Parses a shell word, which is re-lexed from the current token so that words like
+    /// `--version=1.2.3`, `a#b` or `it's` aren't split into Python tokens. Its value has the
+    /// escapes of [`ESCAPED_CHARS`] resolved, while its range keeps the raw text.
     fn parse_proc_single(&mut self, closing: TokenKind) -> Expr {
-        let start = self.node_start();
-        let mut offset = self.node_end();
-        let mut nesting = 0;
-        self.bump_any();
+        self.tokens.re_lex_shell_word(closing);
+        let range = self.current_token_range();
+        self.bump(TokenKind::Word);
+        string_literal(range, unescape_word(&self.source[range]))
+    }
 
-        while !matches!(self.current_token_kind(), tk if
-            tk.is_proc_op() ||
-            offset != self.node_start() ||
-            (tk == closing && nesting == 0) ||
-            matches!(tk, TokenKind::At | TokenKind::Dollar | TokenKind::DollarLParen | TokenKind::AtDollarLParen)
-        ) {
-            if self.current_token_kind() == TokenKind::Lpar {
-                nesting += 1;
+    /// Returns `true` at a redirect like `>`, `2>>`, `e>o`, `&>` or `<>`. A stream name or number
+    /// must be directly followed by the `>`.
+    fn at_redirect(&mut self) -> bool {
+        let range = self.current_token_range();
+        match self.current_token_kind() {
+            TokenKind::Greater | TokenKind::RightShift | TokenKind::Less => true,
+            TokenKind::Name if !REDIR_NAMES.contains(&&self.source[range]) => false,
+            TokenKind::Int | TokenKind::Amper | TokenKind::Name => {
+                matches!(self.peek(), TokenKind::Greater | TokenKind::RightShift)
+                    && self.source[range.end().to_usize()..].starts_with('>')
             }
-            offset = self.node_end();
-            self.bump_any();
+            _ => false,
         }
+    }
 
-        self.to_string_literal(TextRange::new(start, offset))
+    /// Parses a redirect into its key, e.g. `2>>` or `<>`, and its target, which can be any
+    /// command argument or a file descriptor like `&1`.
+    fn parse_redirect(&mut self, progress: &mut ParserProgress, closing: TokenKind) -> DictItem {
+        let start = self.node_start();
+        if !matches!(
+            self.current_token_kind(),
+            TokenKind::Greater | TokenKind::RightShift | TokenKind::Less
+        ) {
+            self.bump_any(); // skip the stream name or number
+        }
+        let operator = self.current_token_kind();
+        self.bump_any();
+        // `<>` opens the target for reading and writing.
+        if operator == TokenKind::Less
+            && self.at(TokenKind::Greater)
+            && self.node_start() == self.prev_token_end
+        {
+            self.bump(TokenKind::Greater);
+        }
+        let key_range = self.node_range(start);
+
+        // The target can be a process substitution, e.g. `2> >(tee err.log)`.
+        self.tokens.re_lex_process_substitution();
+        let value = if self.at(TokenKind::Amper) {
+            self.parse_redirect_fd(key_range)
+        } else if self.at_redirect_end(closing) {
+            self.add_error(ParseErrorType::MissingRedirectTarget, key_range);
+            self.expr_name("Invalid").into()
+        } else {
+            self.parse_proc_arg(progress, closing)
+        };
+
+        DictItem {
+            key: Some(self.to_string_literal(key_range)),
+            value,
+        }
     }
-    fn parse_redirection1(&mut self, closing: TokenKind) -> DictItem {
+
+    /// Parses the `&1` target of a redirect that duplicates a file descriptor, like `2>&1`.
+    fn parse_redirect_fd(&mut self, key_range: TextRange) -> Expr {
         let start = self.node_start();
-        self.bump_any(); // skip the name or number
-        self.bump_any(); // skip the `>`
+        self.bump(TokenKind::Amper);
+        let is_fd = self.at(TokenKind::Int)
+            && self.node_start() == self.prev_token_end
+            && self.source[self.current_token_range()]
+                .bytes()
+                .all(|c| c.is_ascii_digit());
+        if is_fd {
+            self.bump(TokenKind::Int);
+        }
         let range = self.node_range(start);
-        self.parse_redirection(Some(range), closing)
+
+        let key = &self.source[key_range];
+        if !is_fd {
+            self.add_error(ParseErrorType::ExpectedFileDescriptor, range);
+        } else if key.ends_with(">>") || key.starts_with(['&', '<']) {
+            self.add_error(
+                ParseErrorType::InvalidRedirectDuplication,
+                TextRange::new(key_range.start(), range.end()),
+            );
+        }
+        self.to_string_literal(range)
     }
-    fn parse_redirection(&mut self, key_range: Option<TextRange>, closing: TokenKind) -> DictItem {
-        let range = if let Some(key_range) = key_range {
-            key_range
-        } else {
-            let range = self.current_token_range();
-            self.bump_any();
-            range
-        };
 
-        let key = Some(self.to_string_literal(range));
-        let value = self.parse_proc_single(closing);
+    /// Returns `true` if the command ends or another redirect starts where the target of a
+    /// redirect is expected.
+    fn at_redirect_end(&self, closing: TokenKind) -> bool {
+        let tk = self.current_token_kind();
+        tk == closing
+            || matches!(
+                tk,
+                TokenKind::EndOfFile
+                    | TokenKind::Semi
+                    | TokenKind::Greater
+                    | TokenKind::RightShift
+                    | TokenKind::Less
+            )
+            || tk.is_any_newline()
+            || self.at_proc_line_break()
+            || self.at_cmd_separator()
+    }
 
-        DictItem { key, value }
+    /// Returns `true` at an `e|` or `err|`, which pipes the standard error of a command.
+    fn at_stderr_pipe(&self) -> bool {
+        let range = self.current_token_range();
+        if !self.at(TokenKind::Name) || !matches!(&self.source[range], "e" | "err") {
+            return false;
+        }
+        // The `|` must be its own token right after the name, and not a `||` or `|=`.
+        let mut lexer = Lexer::new(self.source, Mode::ParenthesizedExpression, range.end());
+        lexer.next_token() == TokenKind::Vbar && lexer.current_range().start() == range.end()
     }
+
     pub(super) fn parse_decorator_or_interpolation(&mut self) -> Expr {
         self.bump_any(); // skip the `@`
         match self.current_token_kind() {
@@ -233,20 +652,33 @@ impl Parser<'_> {
                     .call0(vec![expr], range)
                     .into()
             }
-            TokenKind::Name if self.peek() == TokenKind::String => {
-                let start = self.node_start();
-                let name = Expr::from(self.parse_name(ExpressionContext::default()));
-                let string = self.parse_strings();
-                let range = self.node_range(start);
-                self.xonsh_attr("Pattern")
-                    .call0(vec![string], range)
-                    .attr("invoke", range)
-                    .call0(vec![name], range)
+            TokenKind::Name
+                if matches!(self.peek(), TokenKind::String | TokenKind::FStringStart) =>
+            {
+                self.parse_pattern_invoke()
                     .star(self.node_range(self.node_start()))
             }
-            _ => unreachable!("Expected to parse a name and a string"),
+            kind => {
+                self.add_error(
+                    ParseErrorType::InvalidInterpolation(kind),
+                    self.current_token_range(),
+                );
+                self.expr_name("Invalid").into()
+            }
         }
     }
+    /// Parses the name and pattern after the `@` of ``@func`.*` `` into
+    /// `__xonsh__.Pattern('.*').invoke(func)`.
+    pub(super) fn parse_pattern_invoke(&mut self) -> ExprWrap {
+        let start = self.node_start();
+        let name = Expr::from(self.parse_name(ExpressionContext::default()));
+        let string = self.parse_strings();
+        let range = self.node_range(start);
+        self.xonsh_attr("Pattern")
+            .call0(vec![string], range)
+            .attr("invoke", range)
+            .call0(vec![name], range)
+    }
     /// consume any tokens until the closing token or `is_macro_end` and strip whitespace
     pub(super) fn parse_proc_macro(&mut self, closing: TokenKind) -> Expr {
         self.bump_any(); // skip the `!`
@@ -276,6 +708,10 @@ impl Parser<'_> {
         };
 
         while f(self.current_token_kind(), nesting) {
+            // The rest of the command ends with the line.
+            if self.at(TokenKind::EndOfFile) || (nesting == 0 && self.at_line_break()) {
+                break;
+            }
             if is_opening(&self.current_token_kind()) {
                 nesting += 1;
             }
@@ -291,6 +727,75 @@ impl Parser<'_> {
         range.end()
     }
 
+    /// Returns `true` at a `|`, `e|`, `&&`, `||`, `and` or `or` that ends a command.
+    pub(super) fn at_cmd_separator(&self) -> bool {
+        self.at(TokenKind::Vbar)
+            || self.at_stderr_pipe()
+            || self.current_token_kind().as_bool_operator().is_some()
+    }
+
+    /// Returns `true` if the current token starts a new line inside brackets.
+    pub(super) fn at_line_break(&self) -> bool {
+        self.tokens.after_non_logical_newline()
+    }
+
+    /// Returns `true` at a line break that ends the arguments of a command whose bracket is never
+    /// closed, because the next line isn't indented more than the line where the command starts.
+    pub(super) fn at_proc_line_break(&self) -> bool {
+        self.at_line_break()
+            && self.proc_indent.is_some_and(|indent| {
+                self.line_indent(self.current_token_range().start()) <= indent
+            })
+    }
+
+    /// Returns `true` if the bracket of a subprocess whose command starts at `start` is closed
+    /// later in the source, or for a bare command, if the brackets opened in its arguments are.
+    fn proc_is_closed(&self, start: TextSize, closing: TokenKind) -> bool {
+        let is_bare = closing == TokenKind::Newline;
+        let mut open = u32::from(!is_bare);
+        let mut lexer = Lexer::new(self.source, Mode::ParenthesizedExpression, start);
+        loop {
+            let nesting = lexer.nesting();
+            match lexer.next_token() {
+                TokenKind::EndOfFile => return open == 0,
+                TokenKind::Rpar | TokenKind::Rsqb | TokenKind::Rbrace => {
+                    open = open.saturating_sub(1);
+                    if open == 0 && !is_bare {
+                        return true;
+                    }
+                }
+                TokenKind::Newline | TokenKind::NonLogicalNewline if is_bare && open == 0 => {
+                    return true;
+                }
+                _ if lexer.nesting() > nesting => open += 1,
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the indentation of the line that contains the given offset, in bytes.
+    pub(super) fn line_indent(&self, offset: TextSize) -> usize {
+        let line_start = self.source[..offset.to_usize()]
+            .rfind(['\n', '\r'])
+            .map_or(0, |newline| newline + 1);
+        let line = &self.source[line_start..];
+        line.len() - line.trim_start_matches([' ', '\t', '\x0C']).len()
+    }
+
+    /// Re-lexes the line break before the current token as the end of the logical line, closing
+    /// the brackets that were opened since the lexer was at the given nesting level.
+    ///
+    /// Returns `true` if the line break was re-lexed as the end of the logical line.
+    fn recover_at_line_break(&mut self, nesting: u32) -> bool {
+        if !self.at_line_break() {
+            return false;
+        }
+        while self.tokens.nesting() > nesting && self.at_line_break() {
+            self.tokens.re_lex_logical_token();
+        }
+        !self.at_line_break()
+    }
+
     /// Creates a xonsh attribute expression.
     fn xonsh_attr(&mut self, name: impl Into<Name>) -> ExprWrap {
         self.expr_name("__xonsh__")
@@ -323,10 +828,7 @@ impl Parser<'_> {
             self.to_string_literal(range)
         } else {
             self.add_error(
-                ParseErrorType::OtherError(format!(
-                    "Expected an Environment variable name, got {}",
-                    self.current_token_kind()
-                )),
+                ParseErrorType::InvalidEnvironmentVariableName(self.current_token_kind()),
                 self.current_token_range(),
             );
             self.expr_name("Invalid").into()
@@ -340,29 +842,47 @@ impl Parser<'_> {
         };
         Expr::Subscript(ast)
     }
+    /// Parses an attribute of the xonsh session like `@.env`, which is `__xonsh__.env`.
+    pub(super) fn parse_interface_attr(&mut self, context: ExpressionContext) -> Expr {
+        let start = self.node_start();
+        let interface = self.expr_name("__xonsh__");
+        self.bump(TokenKind::AtDot);
+        let attr = self.parse_identifier_with_context(context);
+        Expr::Attribute(ast::ExprAttribute {
+            value: Box::new(interface.into()),
+            attr,
+            ctx: ExprContext::Load,
+            range: self.node_range(start),
+            node_index: AtomicNodeIndex::NONE,
+        })
+    }
     pub(super) fn parse_env_expr(&mut self) -> Expr {
+        let start = self.node_start();
+        // The nesting outside of the `${`, to recover to if it isn't closed.
+        let nesting = self.tokens.nesting().saturating_sub(1);
         self.bump(TokenKind::DollarLBrace);
         let attr = self.xonsh_attr("env");
 
         // Slice range doesn't include the `[` token.
         let slice_start = self.node_start();
 
-        let slice: Expr = if self.eat(TokenKind::Rbrace) {
-            // Create an error when receiving an empty slice to parse, e.g. `x[]`
-            let slice_range = self.node_range(slice_start);
+        let slice: Expr = if self.at(TokenKind::Rbrace) {
+            // Create an error when receiving an empty slice to parse, e.g. `${}`
             self.add_error(
-                ParseErrorType::OtherError(format!(
-                    "Expected an Environment variable name or expression, got {}",
-                    self.current_token_kind()
-                )),
-                slice_range,
+                ParseErrorType::EmptyEnvironmentExpression,
+                self.current_token_range(),
             );
             self.expr_name("Invalid").into()
         } else {
-            self.parse_slice(TokenKind::Rbrace)
+            self.with_recovery_context(RecoveryContextKind::EnvExpression, |p| {
+                p.parse_slice(TokenKind::Rbrace)
+            })
         };
 
-        self.bump(TokenKind::Rbrace);
+        self.recover_at_line_break(nesting);
+        if self.expect(TokenKind::Rbrace) {
+            self.check_shell_default_value(self.node_range(start));
+        }
 
         let ast = ast::ExprSubscript {
             value: Box::new(attr.into()),
@@ -379,24 +899,39 @@ impl Parser<'_> {
         start: TextSize,
         flags: TokenFlags,
     ) -> Expr {
-        if flags.intersects(TokenFlags::PATH_STRING) {
-            return self
-                .xonsh_attr("path_literal")
-                .call0(vec![expr], self.node_range(start))
-                .into();
-        } else if flags.intersects(TokenFlags::GLOB_STRING) {
+        let range = self.node_range(start);
+        if flags.intersects(TokenFlags::BACKTICK_STRING | TokenFlags::GLOB_STRING) {
+            let glob = flags.intersects(TokenFlags::GLOB_STRING);
+            self.check_pattern_literal(&expr, glob);
+            let method = if glob { "glob" } else { "regex" };
+            // With backticks, `p` returns the matches as paths, like ``pg`*.py` ``.
+            let keywords = if flags.contains(TokenFlags::BACKTICK_STRING | TokenFlags::PATH_STRING)
+            {
+                vec![ast::Keyword {
+                    arg: Some(self.to_identifier("paths")),
+                    value: self.literal_true(),
+                    range,
+                    node_index: AtomicNodeIndex::NONE,
+                }]
+            } else {
+                vec![]
+            };
+            let arguments = ast::Arguments {
+                range,
+                args: Box::default(),
+                keywords: keywords.into(),
+                node_index: AtomicNodeIndex::NONE,
+            };
             return self
                 .xonsh_attr("Pattern")
-                .call0(vec![expr], self.node_range(start))
-                .attr("glob", self.node_range(start))
-                .call_empty(self.node_range(start))
+                .call0(vec![expr], range)
+                .attr(method, range)
+                .call(arguments, range)
                 .into();
-        } else if flags.intersects(TokenFlags::BACKTICK_STRING) {
+        } else if flags.intersects(TokenFlags::PATH_STRING) {
             return self
-                .xonsh_attr("Pattern")
-                .call0(vec![expr], self.node_range(start))
-                .attr("regex", self.node_range(start))
-                .call_empty(self.node_range(start))
+                .xonsh_attr("path_literal")
+                .call0(vec![expr], range)
                 .into();
         }
 
@@ -425,15 +960,20 @@ impl Parser<'_> {
         self.xonsh_attr(method).call0(args, range).into()
     }
     pub(super) fn parse_call_macro(&mut self, lhs: Expr, start: TextSize) -> Expr {
+        // The nesting outside of the `!(`, to recover to if it isn't closed.
+        let nesting = self.tokens.nesting().saturating_sub(1);
         self.bump(TokenKind::BangLParen);
         let closing = TokenKind::Rpar;
-        let mut progress = ParserProgress::default();
 
-        let mut inner_args = vec![];
-        while !self.at(closing) {
-            inner_args.push(self.parse_call_macro_arg(closing));
-            progress.assert_progressing(self);
+        let checkpoint = self.checkpoint();
+        let mut inner_args = self.parse_call_macro_args(MacroArgumentsKind::Multiline);
+        if !self.at(closing) {
+            // Without the closing `)`, the arguments end with the line of the call.
+            self.rewind(checkpoint);
+            inner_args = self.parse_call_macro_args(MacroArgumentsKind::SingleLine);
+            self.recover_at_line_break(nesting);
         }
+
         let range = self.node_range(start);
         let args = vec![
             lhs,
@@ -447,18 +987,39 @@ impl Parser<'_> {
             self.expr_name("globals").call_empty(range).into(),
             self.expr_name("locals").call_empty(range).into(),
         ];
-        self.bump(closing);
+        self.expect(closing);
         self.xonsh_attr("call_macro").call0(args, range).into()
     }
+
+    fn parse_call_macro_args(&mut self, kind: MacroArgumentsKind) -> Vec<Expr> {
+        self.parse_comma_separated_list_into_vec(RecoveryContextKind::MacroArguments(kind), |p| {
+            p.parse_call_macro_arg(kind)
+        })
+    }
+
     #[inline]
-    fn parse_call_macro_arg(&mut self, closing: TokenKind) -> Expr {
+    fn parse_call_macro_arg(&mut self, kind: MacroArgumentsKind) -> Expr {
+        let closing = TokenKind::Rpar;
         let start = self.node_start();
+        if self.at(TokenKind::Comma) {
+            // An empty argument, e.g. `f!(x, , y)`
+            self.add_error(
+                ParseErrorType::InvalidMacroArgument(TokenKind::Comma),
+                self.current_token_range(),
+            );
+            return self.to_string_literal(TextRange::empty(start));
+        }
         let end = {
             let mut nesting = vec![];
             let mut end = self.current_token_range().end();
 
-            while !nesting.is_empty() || self.current_token_kind() != closing {
+            loop {
                 let tk = self.current_token_kind();
+                if matches!(tk, TokenKind::Newline | TokenKind::EndOfFile)
+                    || (kind == MacroArgumentsKind::SingleLine && self.at_line_break())
+                {
+                    break;
+                }
                 if let Some(inner) = tk.get_closer() {
                     nesting.push(inner);
                 } else if let Some(last) = nesting.last() {
@@ -474,9 +1035,6 @@ impl Parser<'_> {
             }
             end
         };
-        if self.at(TokenKind::Comma) {
-            self.bump(TokenKind::Comma);
-        }
         let range = TextRange::new(start, end);
         self.to_string_literal(range)
     }
@@ -497,27 +1055,34 @@ impl Parser<'_> {
             usize::MIN
         };
 
-        {
+        let outer_indent = std::mem::replace(&mut self.with_macro_indent, min_indent);
+        self.with_recovery_context(RecoveryContextKind::WithMacroSuite, |p| {
             // loop until dedent
             let mut indent_level = 0;
 
             loop {
-                if self.at(TokenKind::EndOfFile) {
-                    break;
-                }
-                if self.at(TokenKind::Dedent) && indent_level < 1 {
+                if p.at(TokenKind::EndOfFile) {
                     break;
                 }
-                let tk = self.current_token_kind();
-
-                if tk == TokenKind::Indent {
-                    indent_level += 1;
-                } else if indent_level > 0 && tk == TokenKind::Dedent {
+                if RecoveryContextKind::WithMacroSuite.is_list_terminator(p) {
+                    if p.recover_at_line_break(0) {
+                        p.add_error(
+                            ParseErrorType::UnclosedWithMacroSuite,
+                            p.current_token_range(),
+                        );
+                        continue;
+                    }
+                    if indent_level < 1 {
+                        break;
+                    }
                     indent_level -= 1;
+                } else if p.at(TokenKind::Indent) {
+                    indent_level += 1;
                 }
-                self.bump_any();
+                p.bump_any();
             }
-        }
+        });
+        self.with_macro_indent = outer_indent;
         let range = self.node_range(start);
         let body = {
             let pass = ast::StmtPass {
@@ -582,7 +1147,63 @@ fn dedent(input: &str, min_indent: usize) -> String {
         .join("\n")
 }
 
-fn string_literal(range: TextRange, value: String) -> Expr {
+/// Returns `true` for the token after the targets of an assignment, like `=` in `x = 1`, `:` in
+/// `x: int` or `+=`.
+fn is_assignment(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::Equal | TokenKind::Colon)
+        || kind.as_augmented_assign_operator().is_some()
+}
+
+fn is_name_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+/// Strips the spaces, tabs and backslash continuations from the start of the text.
+fn trim_blanks(mut text: &str) -> &str {
+    loop {
+        let trimmed = text.trim_start_matches([' ', '\t']);
+        match trimmed.strip_prefix('\\') {
+            Some(rest) if rest.starts_with(['\n', '\r']) => {
+                text = rest.strip_prefix("\r\n").unwrap_or(&rest[1..]);
+            }
+            _ => return trimmed,
+        }
+    }
+}
+
+/// Resolves the backslash escapes of a shell word, see [`ESCAPED_CHARS`].
+pub(super) fn unescape_word(raw: &str) -> String {
+    let mut value = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\'
+            && let Some(&next) = chars.peek()
+            && ESCAPED_CHARS.contains(next)
+        {
+            value.push(next);
+            chars.next();
+        } else {
+            value.push(c);
+        }
+    }
+    value
+}
+
+/// Joins the adjacent parts of a command argument, like `a$X@(y)`, with `+`.
+pub(super) fn concat_parts(first: Expr, rest: Vec<Expr>) -> Expr {
+    rest.into_iter().fold(first, |expr, next| {
+        let range = TextRange::new(expr.range().start(), next.range().end());
+        Expr::BinOp(ast::ExprBinOp {
+            left: Box::new(expr),
+            op: ast::Operator::Add,
+            right: Box::new(next),
+            range,
+            node_index: AtomicNodeIndex::NONE,
+        })
+    })
+}
+
+pub(super) fn string_literal(range: TextRange, value: String) -> Expr {
     let literal = ast::StringLiteral {
         value: value.into_boxed_str(),
         range,
@@ -1,6 +1,10 @@
 use ruff_python_ast::{Expr, InterpolatedStringElement, IpyEscapeKind, Number, Stmt};
+use ruff_text_size::Ranged;
 
-use crate::{Mode, ParseErrorType, ParseOptions, parse, parse_expression, parse_module};
+use crate::{
+    LexicalErrorType, Mode, ParseErrorType, ParseOptions, PatternErrorType, PatternSeverity,
+    ShellSyntaxKind, TokenKind, parse, parse_expression, parse_module, parse_unchecked,
+};
 
 #[test]
 fn test_modes() {
@@ -224,6 +228,608 @@ fn test_xonsh_procs() {
 
     insta::assert_debug_snapshot!(suite);
 }
+
+#[test]
+fn test_xonsh_recovery() {
+    // An unclosed subprocess, environment or macro construct reports a single error and parsing
+    // resumes on the next line.
+    for source in [
+        "x = $(ls | grep\ny = 1\n",
+        "x = ![echo a\ny = 1\n",
+        "ls foo(bar\ny = 1\n",
+        "x = ${x\ny = 1\n",
+        "f!(x, y\ny = 1\n",
+        "with! x:\n    foo(\ny = 1\n",
+    ] {
+        let parsed = parse_unchecked(source, ParseOptions::from(Mode::Module))
+            .try_into_module()
+            .unwrap();
+        assert_eq!(parsed.errors().len(), 1, "source: {source:?}");
+
+        let Some(Stmt::Assign(assign)) = parsed.suite().last() else {
+            panic!("expected an assignment at the end of {source:?}");
+        };
+        assert!(
+            matches!(&assign.targets[..], [Expr::Name(name)] if name.id == "y"),
+            "source: {source:?}"
+        );
+    }
+}
+
+#[test]
+fn test_bare_proc_statements() {
+    // A `;` or newline ends a bare command and parsing goes on with the next statement.
+    for (source, count) in [
+        ("echo hi; ls tmp; x = 1\n", 3),
+        ("echo hi\nx = 1\n", 2),
+        ("make build &; x = 1\n", 2),
+        ("make && make install; x = 1\n", 2),
+    ] {
+        let parsed = parse_unchecked(source, ParseOptions::from(Mode::Module))
+            .try_into_module()
+            .unwrap();
+        assert!(
+            parsed.errors().is_empty(),
+            "source: {source:?}, errors: {:?}",
+            parsed.errors()
+        );
+        assert_eq!(parsed.suite().len(), count, "source: {source:?}");
+        assert!(
+            matches!(parsed.suite().last(), Some(Stmt::Assign(_))),
+            "source: {source:?}"
+        );
+    }
+
+    let parsed = parse_unchecked("if x: echo a; y = 1\n", ParseOptions::from(Mode::Module))
+        .try_into_module()
+        .unwrap();
+    assert!(parsed.errors().is_empty(), "errors: {:?}", parsed.errors());
+    let [Stmt::If(if_stmt)] = parsed.suite() else {
+        panic!("expected a single if statement");
+    };
+    assert!(matches!(
+        &if_stmt.body[..],
+        [Stmt::Expr(_), Stmt::Assign(_)]
+    ));
+}
+
+#[test]
+fn test_bare_proc_detection() {
+    let is_bare_proc = |source: &str| {
+        let parsed = parse_module(source).unwrap();
+        let [Stmt::Expr(stmt)] = parsed.suite() else {
+            return false;
+        };
+        let Expr::Call(call) = stmt.value.as_ref() else {
+            return false;
+        };
+        matches!(call.func.as_ref(), Expr::Attribute(attr) if attr.attr.as_str() == "hide")
+    };
+    for source in [
+        "./build.sh --fast\n",
+        "../configure\n",
+        "/usr/bin/env python\n",
+        "~/bin/tool x\n",
+        "cd ..\n",
+        "ls -la ~/x\n",
+        "ls /tmp/\n",
+    ] {
+        assert!(is_bare_proc(source), "source: {source:?}");
+    }
+    // Valid Python keeps its meaning.
+    for source in ["ls -l\n", "a / b\n", "a /b\n", "x -1\n", "a .b\n", "~x\n"] {
+        assert!(!is_bare_proc(source), "source: {source:?}");
+    }
+}
+
+#[test]
+fn test_line_continuations() {
+    let command_args = |source: &str| {
+        let parsed = parse_module(source).unwrap();
+        let Some(stmt) = parsed.suite().last() else {
+            panic!("expected a statement for {source:?}");
+        };
+        let value = match stmt {
+            Stmt::Expr(stmt) => stmt.value.as_ref(),
+            Stmt::Assign(stmt) => stmt.value.as_ref(),
+            _ => panic!("expected a command for {source:?}"),
+        };
+        let Expr::Call(call) = value else {
+            panic!("expected a call for {source:?}");
+        };
+        let Expr::Attribute(attr) = call.func.as_ref() else {
+            panic!("expected a method call for {source:?}");
+        };
+        let Expr::Call(cmd) = attr.value.as_ref() else {
+            panic!("expected a command for {source:?}");
+        };
+        cmd.arguments
+            .args
+            .iter()
+            .map(|arg| &source[arg.range()])
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        command_args("docker run \\\n    --rm \\\n    -it image\n"),
+        ["docker", "run", "--rm", "-it", "image"]
+    );
+    assert_eq!(command_args("ls \\\n    -la ~/x\n"), ["ls", "-la", "~/x"]);
+    assert_eq!(
+        command_args("x = $(docker run\n    --rm\n    image\n)\n"),
+        ["docker", "run", "--rm", "image"]
+    );
+    assert_eq!(
+        command_args("ls foo(bar\n    baz)\n"),
+        ["ls", "foo(bar", "baz)"]
+    );
+    assert_eq!(command_args("![echo a >\n    out.txt\n]\n"), ["echo", "a"]);
+    // The lines inside a bracket that is closed belong to the command, however indented.
+    assert_eq!(
+        command_args("x = $(docker run\n--rm img)\n"),
+        ["docker", "run", "--rm", "img"]
+    );
+    assert_eq!(
+        command_args("ls foo(bar\nbaz)\n"),
+        ["ls", "foo(bar", "baz)"]
+    );
+}
+
+#[test]
+fn test_shell_words() {
+    let words = |source: &str| {
+        let parsed = parse_expression(source).unwrap().into_expr();
+        let Expr::Call(out) = parsed else {
+            panic!("expected a call for {source:?}");
+        };
+        let Expr::Attribute(attr) = *out.func else {
+            panic!("expected a method call for {source:?}");
+        };
+        let Expr::Call(cmd) = *attr.value else {
+            panic!("expected a command for {source:?}");
+        };
+        cmd.arguments
+            .args
+            .iter()
+            .map(|arg| match arg {
+                Expr::StringLiteral(string) => string.value.to_str().to_string(),
+                _ => panic!("expected a word in {source:?}"),
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        words("$(pip --version=1.2.3 a#b it's 08 x)"),
+        ["pip", "--version=1.2.3", "a#b", "it's", "08", "x"]
+    );
+    assert_eq!(words("$(ls foo(bar) [ab]*)"), ["ls", "foo(bar)", "[ab]*"]);
+    assert_eq!(words("$(echo a]b)"), ["echo", "a]b"]);
+    assert_eq!(words("![echo a)b]"), ["echo", "a)b"]);
+    // Backslashes escape the characters that would end or split a word, and quoted parts are
+    // joined to the word around them.
+    assert_eq!(
+        words(r#"$(ls My\ Documents \$HOME C:\Users it\'s "foo"'bar'baz --msg='a b')"#),
+        [
+            "ls",
+            "My Documents",
+            "$HOME",
+            r"C:\Users",
+            "it's",
+            "foobarbaz",
+            "--msg=a b"
+        ]
+    );
+}
+
+#[test]
+fn test_shell_word_tokens() {
+    let source = "$(pip --version=1.2.3 a#b it's 08)";
+    let parsed = parse_expression(source).unwrap();
+    let words = parsed
+        .tokens()
+        .iter()
+        .filter(|token| token.kind() == TokenKind::Word)
+        .map(|token| &source[token.range()])
+        .collect::<Vec<_>>();
+    assert_eq!(words, ["pip", "--version=1.2.3", "a#b", "it's", "08"]);
+    // The Python tokens of the words, like the comment in `a#b`, are gone.
+    assert!(!parsed.tokens().iter().any(|token| matches!(
+        token.kind(),
+        TokenKind::Comment | TokenKind::String | TokenKind::Float | TokenKind::Int
+    )));
+}
+
+#[test]
+fn test_string_prefixes() {
+    // Returns the lowering of a path, glob or regex literal as `method(kind, paths=...)`.
+    let special = |source: &str| {
+        let Expr::Call(call) = parse_expression(source).unwrap().into_expr() else {
+            panic!("expected a call for {source:?}");
+        };
+        let Expr::Attribute(attr) = *call.func else {
+            panic!("expected a method call for {source:?}");
+        };
+        let (method, arg) = match (attr.attr.as_str(), &*call.arguments.args) {
+            ("path_literal", [arg]) => ("path_literal", arg),
+            (method, []) => {
+                let Expr::Call(pattern) = &*attr.value else {
+                    panic!("expected a pattern for {source:?}");
+                };
+                (method, &pattern.arguments.args[0])
+            }
+            _ => panic!("unexpected call for {source:?}"),
+        };
+        let kind = match arg {
+            Expr::StringLiteral(_) => "str",
+            Expr::FString(_) => "fstr",
+            _ => panic!("expected a string in {source:?}"),
+        };
+        let paths = call.arguments.find_keyword("paths").is_some();
+        format!("{method}({kind}, paths={paths})")
+    };
+    for (source, expected) in [
+        (r#"pf"{x}/y""#, "path_literal(fstr, paths=false)"),
+        (r#"fp"{x}/y""#, "path_literal(fstr, paths=false)"),
+        (r#"pr"C:\x""#, "path_literal(str, paths=false)"),
+        (r#"Rp"C:\x""#, "path_literal(str, paths=false)"),
+        (r#"pfr"{x}\y""#, "path_literal(fstr, paths=false)"),
+        (r#"gf"{x}*""#, "glob(fstr, paths=false)"),
+        (r#"fg"{x}*""#, "glob(fstr, paths=false)"),
+        ("r`[a-z]+`", "regex(str, paths=false)"),
+        ("f`{x}+`", "regex(fstr, paths=false)"),
+        ("g`*.py`", "glob(str, paths=false)"),
+        ("p`.*`", "regex(str, paths=true)"),
+        ("pg`*.py`", "glob(str, paths=true)"),
+        ("pfr`{x}\\d`", "regex(fstr, paths=true)"),
+    ] {
+        assert_eq!(special(source), expected, "source: {source:?}");
+    }
+
+    for source in [
+        r#"pb"x""#, r#"bu"x""#, r#"pg"x""#, r#"pt"x""#, r#"rR"x""#, r#"ft"x""#, "b`x`", "tp`x`",
+    ] {
+        let parsed = parse_expression(source);
+        let error = parsed.unwrap_err();
+        let prefix = &source[..source.find(['"', '`']).unwrap()];
+        assert_eq!(
+            error.error,
+            ParseErrorType::Lexical(LexicalErrorType::InvalidStringPrefix(prefix.into())),
+            "source: {source:?}"
+        );
+        assert_eq!(&source[error.location], prefix);
+    }
+    assert!(parse_expression("print(@foo`.*`)").is_ok());
+}
+
+#[test]
+fn test_pattern_literals() {
+    let invalid = [
+        (
+            "`(a`",
+            ParseErrorType::InvalidRegex(PatternErrorType::UnterminatedGroup),
+            "(",
+        ),
+        (
+            "`a)`",
+            ParseErrorType::InvalidRegex(PatternErrorType::UnbalancedParenthesis),
+            ")",
+        ),
+        (
+            r"`a\q`",
+            ParseErrorType::InvalidRegex(PatternErrorType::BadEscape(r"\q".to_string())),
+            r"\q",
+        ),
+        (
+            r"r`\x4`",
+            ParseErrorType::InvalidRegex(PatternErrorType::BadEscape(r"\x4".to_string())),
+            r"\x4",
+        ),
+        (
+            "`[a-z`",
+            ParseErrorType::InvalidRegex(PatternErrorType::UnterminatedCharacterSet),
+            "[",
+        ),
+        (
+            "`[0-9z-a]`",
+            ParseErrorType::InvalidRegex(PatternErrorType::BadCharacterRange('z', 'a')),
+            "z-a",
+        ),
+        (
+            "f`({x}`",
+            ParseErrorType::InvalidRegex(PatternErrorType::UnterminatedGroup),
+            "(",
+        ),
+        (
+            r#"g"*.[ch""#,
+            ParseErrorType::InvalidGlob(PatternErrorType::UnterminatedCharacterSet),
+            "[",
+        ),
+        // The escapes of the string are resolved before the regex is checked.
+        (
+            r"p`\x28a`",
+            ParseErrorType::InvalidRegex(PatternErrorType::UnterminatedGroup),
+            r"\x28",
+        ),
+        (
+            r#"g"[z-a]""#,
+            ParseErrorType::InvalidGlob(PatternErrorType::BadCharacterRange('z', 'a')),
+            "z-a",
+        ),
+    ];
+    for (source, error, text) in invalid {
+        let parsed = parse_module(source).unwrap();
+        let [warning] = parsed.warnings() else {
+            panic!("expected a warning for {source:?}: {:?}", parsed.warnings());
+        };
+        assert_eq!(warning.error, error, "source: {source:?}");
+        assert_eq!(&source[warning.location], text, "source: {source:?}");
+
+        let options =
+            ParseOptions::from(Mode::Module).with_pattern_severity(PatternSeverity::Error);
+        let err = parse(source, options).unwrap_err();
+        assert_eq!(err.error, error, "source: {source:?}");
+    }
+
+    for source in [
+        r"`(a|b)+[^]x]\d{1,2}\(`",
+        r"`\\(`",
+        "`(?#a(b)c`",
+        "f`({x})[{{1,2}}]`",
+        r#"g"*.[!ch]""#,
+        r#"g"[]a-z]*""#,
+    ] {
+        let parsed = parse_module(source).unwrap();
+        assert!(
+            parsed.warnings().is_empty(),
+            "source: {source:?}: {:?}",
+            parsed.warnings()
+        );
+    }
+
+    // The with items are parsed speculatively, which must not repeat the warning.
+    for source in [
+        "with (`(a`):\n    pass\n",
+        "with (`(a` as f, b):\n    pass\n",
+        "with (`(a`, b) as c:\n    pass\n",
+        "match `(a`, b:\n    case _:\n        pass\n",
+    ] {
+        let parsed = parse_module(source).unwrap();
+        assert_eq!(parsed.warnings().len(), 1, "source: {source:?}");
+    }
+}
+
+#[test]
+fn test_known_names() {
+    let options = ParseOptions::from(Mode::Module).with_known_names(["x", "print"]);
+    let is_bare_proc = |source: &str| {
+        let parsed = parse_unchecked(source, options.clone())
+            .try_into_module()
+            .unwrap();
+        assert!(parsed.errors().is_empty(), "errors: {:?}", parsed.errors());
+        let Some(Stmt::Expr(stmt)) = parsed.suite().last() else {
+            return false;
+        };
+        let Expr::Call(call) = stmt.value.as_ref() else {
+            return false;
+        };
+        matches!(call.func.as_ref(), Expr::Attribute(attr) if attr.attr.as_str() == "hide")
+    };
+    for source in [
+        "ls -l\n",
+        "ls\n",
+        "make -j4 all\n",
+        "x = 1\nls .\n",
+        // Reading a name doesn't bind it.
+        "print(ls)\nls -l\n",
+        "x = f(ls=1)\nls -l\n",
+        "echo ls=1\nls -l\n",
+        "x.ls = 1\nls -l\n",
+    ] {
+        assert!(is_bare_proc(source), "source: {source:?}");
+    }
+    for source in [
+        "x -l\n",
+        "print(ls)\n",
+        "ls = 1\n",
+        "ls += 1\n",
+        "ls.x\n",
+        "ls, y = 1, 2\n",
+        "ls = 1\nls -l\n",
+        "def f(ls):\n    ls -l\n",
+        "for ls in x:\n    ls\n",
+        "ls: int = 1\nls -l\n",
+        "(a, [ls, *b]) = x\nls -l\n",
+        "import ls\nls -l\n",
+        "from m import (x, y as ls)\nls -l\n",
+        "with x as ls:\n    ls -l\n",
+        "global ls\nls -l\n",
+        "if x: ls = 1\nls -l\n",
+        "x = (ls := 1)\nls -l\n",
+    ] {
+        assert!(!is_bare_proc(source), "source: {source:?}");
+    }
+    // Without the names, `ls -l` is a subtraction.
+    assert!(matches!(
+        parse_module("ls -l\n").unwrap().suite(),
+        [Stmt::Expr(stmt)] if stmt.value.is_bin_op_expr()
+    ));
+}
+
+#[test]
+fn test_brace_expansion() {
+    let arg_count = |source: &str| {
+        let expr = parse_expression(source).unwrap().into_expr();
+        let Expr::Call(out) = expr else {
+            panic!("expected a call for {source:?}");
+        };
+        let Expr::Attribute(attr) = *out.func else {
+            panic!("expected a method call for {source:?}");
+        };
+        let Expr::Call(cmd) = *attr.value else {
+            panic!("expected a command for {source:?}");
+        };
+        match &cmd.arguments.args[..] {
+            [_, Expr::List(list)] => Some(list.elts.len()),
+            _ => None,
+        }
+    };
+    assert_eq!(arg_count("$(echo {a,b,c})"), Some(3));
+    assert_eq!(arg_count("$(echo {1..10}x)"), Some(10));
+    assert_eq!(arg_count("$(echo {a,b}{1..3})"), Some(6));
+    // Groups without alternatives and ranges that are too long stay words.
+    assert_eq!(arg_count("$(echo {a})"), None);
+    assert_eq!(arg_count("$(echo {1..100000})"), None);
+    assert_eq!(arg_count("$(echo {1..40}{1..40})"), None);
+    // The whole argument stays a word, even if a part of it could be expanded on its own.
+    assert_eq!(arg_count(&format!("$(echo {})", "{a,b}".repeat(12))), None);
+    assert_eq!(arg_count("$(echo {a,b}{1..100000})"), None);
+}
+
+#[test]
+fn test_xonsh_error_kinds() {
+    for (source, expected) in [
+        (
+            "x = $1\n",
+            ParseErrorType::InvalidEnvironmentVariableName(TokenKind::Int),
+        ),
+        ("x = ${}\n", ParseErrorType::EmptyEnvironmentExpression),
+        (
+            "x = $(ls\ny = 1\n",
+            ParseErrorType::UnclosedSubprocess(TokenKind::Rpar),
+        ),
+        (
+            "ls foo(bar\ny = 1\n",
+            ParseErrorType::UnclosedSubprocess(TokenKind::Newline),
+        ),
+        (
+            "with! x:\n    foo(\ny = 1\n",
+            ParseErrorType::UnclosedWithMacroSuite,
+        ),
+        ("x = $(ls >)\n", ParseErrorType::MissingRedirectTarget),
+        ("x = $(ls > | wc)\n", ParseErrorType::MissingRedirectTarget),
+        ("x = $(ls 2>&x)\n", ParseErrorType::ExpectedFileDescriptor),
+        (
+            "x = $(ls 2>>&1)\n",
+            ParseErrorType::InvalidRedirectDuplication,
+        ),
+        (
+            "x = $(ls <&0)\n",
+            ParseErrorType::InvalidRedirectDuplication,
+        ),
+        (
+            "x = $(ls @x)\n",
+            ParseErrorType::InvalidInterpolation(TokenKind::Name),
+        ),
+        (
+            "f!(x, , y)\n",
+            ParseErrorType::InvalidMacroArgument(TokenKind::Comma),
+        ),
+        ("x = $(sleep 1 & ls)\n", ParseErrorType::BackgroundNotAtEnd),
+        (
+            "x = $(@yaml cat x)\n",
+            ParseErrorType::UnknownSpecModifier("yaml".to_string()),
+        ),
+        (
+            "x = $(curl @json x)\n",
+            ParseErrorType::MisplacedSpecModifier("json".to_string()),
+        ),
+        (
+            "x = $(diff <(a && b) c)\n",
+            ParseErrorType::ChainInProcessSubstitution(TokenKind::DoubleAmp),
+        ),
+    ] {
+        let parsed = parse_unchecked(source, ParseOptions::from(Mode::Module));
+        let error = parsed.errors().first().map(|err| &err.error);
+        assert_eq!(error, Some(&expected), "source: {source:?}");
+    }
+}
+
+#[test]
+fn test_shell_syntax_fixes() {
+    for (source, kind, fix) in [
+        ("export FOO=bar\n", ShellSyntaxKind::Export, "$FOO = 'bar'"),
+        (
+            "if [ -f x ]; then\n    pass\n",
+            ShellSyntaxKind::TestCondition,
+            "![test -f x]:",
+        ),
+        (
+            "x = $((1 + (2)))\n",
+            ShellSyntaxKind::ArithmeticExpansion,
+            "(1 + (2))",
+        ),
+        (
+            "x = ${VAR:-default}\n",
+            ShellSyntaxKind::DefaultValue,
+            "${...}.get('VAR', 'default')",
+        ),
+        (
+            "for f in *.txt; do\n    pass\n",
+            ShellSyntaxKind::ForDo,
+            "g'*.txt':",
+        ),
+    ] {
+        let parsed = parse_unchecked(source, ParseOptions::from(Mode::Module));
+        let Some(ParseErrorType::ShellSyntax(error)) = parsed.errors().first().map(|e| &e.error)
+        else {
+            panic!("expected a shell syntax error for {source:?}");
+        };
+        assert_eq!(error.kind, kind, "source: {source:?}");
+        assert_eq!(error.fix, fix, "source: {source:?}");
+    }
+}
+
+#[test]
+fn xonsh_token_streams_do_not_panic() {
+    // Pieces of xonsh syntax, including unbalanced brackets and misplaced operators, that are
+    // glued together at random to make malformed sources.
+    const FRAGMENTS: &[&str] = &[
+        "$(", "$[", "${", "![", "!(", "@(", "@$(", "(", "[", "{", ")", "]", "}", "|", "&", "&&",
+        "||", ";", ">", ">>", "<", "2>", "e>o", "a>", "@", "@x", "g`*`", "$", "$X", "echo", "ls",
+        "f!", "with! x:", ":", ",", "=", "'s'", "\"", "f'{", "}'", "\n", "\n    ", "\\\n", " ",
+        "# c", "1", "x.y", "!", "and", "or", "<(", ">(", "{a,b}", "{1..3}", "{a,", "..", "@json",
+        "@lines", "@.", "@.env", "2>&1", "|&", "o+e>", "\\ ", "--x=1.2", "a\"b\"", "pf\"", "rg\"",
+        "pr`", "`", "@f\"", "X=1", "|=", " e", "err|",
+    ];
+
+    // A xorshift generator, so that every run checks the same sources.
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for _ in 0..5_000 {
+        let len = next() % 16 + 1;
+        let source: String = (0..len)
+            .map(|_| FRAGMENTS[usize::try_from(next() % FRAGMENTS.len() as u64).unwrap()])
+            .collect();
+        // Every other source is parsed with known names and strict patterns, so that lines
+        // starting with unknown names are re-lexed as commands.
+        let options = if len % 2 == 0 {
+            ParseOptions::from(Mode::Module)
+        } else {
+            ParseOptions::from(Mode::Module)
+                .with_known_names(["x"])
+                .with_pattern_severity(PatternSeverity::Error)
+        };
+        let result = std::panic::catch_unwind(|| {
+            parse_unchecked(&source, options);
+        });
+        assert!(result.is_ok(), "the parser panicked on {source:?}");
+    }
+}
+
+#[test]
+fn stderr_pipe_is_not_taken_from_other_operators() {
+    for source in ["ls e|= x\n", "x = $(ls e|= x)\n", "x = $(ls err||y)\n"] {
+        let result = std::panic::catch_unwind(|| {
+            parse_unchecked(source, ParseOptions::from(Mode::Module));
+        });
+        assert!(result.is_ok(), "the parser panicked on {source:?}");
+    }
+}
+
 #[test]
 fn test_ipython_escape_commands() {
     let parsed = parse(
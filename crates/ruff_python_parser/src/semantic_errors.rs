@@ -1289,7 +1289,13 @@ pub struct SemanticSyntaxError {
 
 impl Display for SemanticSyntaxError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.kind {
+        self.kind.fmt(f)
+    }
+}
+
+impl Display for SemanticSyntaxErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
             SemanticSyntaxErrorKind::LateFutureImport => {
                 f.write_str("__future__ imports must be at the top of the file")
             }
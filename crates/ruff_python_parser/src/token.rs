@@ -153,6 +153,9 @@ pub enum TokenKind {
     TStringEnd,
     /// Token kind for a `IPython` escape command.
     IpyEscapeCommand,
+    /// Token kind for a shell word in the arguments of a command, like `--version=1.2.3` or
+    /// `it's`. The lexer only produces it when the parser re-lexes a token in a command.
+    Word,
     /// Token kind for a comment. These are filtered out of the token stream prior to parsing.
     Comment,
     /// Token kind for a newline.
@@ -274,7 +277,10 @@ pub enum TokenKind {
     DollarLSqb,     // "$["
     DollarLBrace,   // "${"
     AtDollarLParen, // "@$("
+    AtDot,          // "@.", the start of an attribute of the xonsh session like `@.env`
     BackTick,       // "`"
+    LessLpar,       // "<(", only in the arguments of a command
+    GreaterLpar,    // ">(", only in the arguments of a command
 
     // The keywords should be sorted in alphabetical order. If the boundary tokens for the
     // "Keywords" and "Soft keywords" group change, update the related methods on `TokenKind`.
@@ -451,8 +457,11 @@ impl TokenKind {
         matches!(self, TokenKind::Newline | TokenKind::Dedent)
     }
     pub const fn is_open_paren(&self) -> bool {
-        use TokenKind::{AtDollarLParen, BangLParen, DollarLParen, Lpar};
-        matches!(self, Lpar | BangLParen | DollarLParen | AtDollarLParen)
+        use TokenKind::{AtDollarLParen, BangLParen, DollarLParen, GreaterLpar, LessLpar, Lpar};
+        matches!(
+            self,
+            Lpar | BangLParen | DollarLParen | AtDollarLParen | LessLpar | GreaterLpar
+        )
     }
     pub const fn get_closer(&self) -> Option<Self> {
         if self.is_open_paren() {
@@ -699,6 +708,7 @@ impl fmt::Display for TokenKind {
             TokenKind::TStringMiddle => "TStringMiddle",
             TokenKind::TStringEnd => "TStringEnd",
             TokenKind::IpyEscapeCommand => "IPython escape command",
+            TokenKind::Word => "shell word",
             TokenKind::Comment => "comment",
             TokenKind::Question => "`?`",
             TokenKind::Exclamation => "`!`",
@@ -797,7 +807,10 @@ impl fmt::Display for TokenKind {
             TokenKind::DollarLSqb => "'$['",
             TokenKind::DollarLBrace => "'${'",
             TokenKind::AtDollarLParen => "'@$('",
+            TokenKind::AtDot => "'@.'",
             TokenKind::BackTick => "'`'",
+            TokenKind::LessLpar => "'<('",
+            TokenKind::GreaterLpar => "'>('",
         };
         f.write_str(value)
     }
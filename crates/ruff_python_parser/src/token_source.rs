@@ -58,6 +58,16 @@ impl<'src> TokenSource<'src> {
         self.lexer.current_flags()
     }
 
+    /// Returns `true` if a non-logical newline separates the current token from the previous
+    /// non-trivia token, i.e. the current token starts a new line inside brackets.
+    pub(crate) fn after_non_logical_newline(&self) -> bool {
+        self.tokens
+            .iter()
+            .rev()
+            .find(|token| token.kind() != TokenKind::Comment)
+            .is_some_and(|token| token.kind() == TokenKind::NonLogicalNewline)
+    }
+
     /// Calls the underlying [`re_lex_logical_token`] method on the lexer with the new lexer
     /// position and updates the token vector accordingly.
     ///
@@ -132,6 +142,18 @@ impl<'src> TokenSource<'src> {
         self.lexer.re_lex_raw_string_in_format_spec();
     }
 
+    /// Re-lexes the current `<` or `>` token as the start of a process substitution, see
+    /// [`Lexer::re_lex_process_substitution`].
+    pub(crate) fn re_lex_process_substitution(&mut self) -> bool {
+        self.lexer.re_lex_process_substitution()
+    }
+
+    /// Re-lexes the current token as the start of a shell word, see
+    /// [`Lexer::re_lex_shell_word`].
+    pub(crate) fn re_lex_shell_word(&mut self, closing: TokenKind) {
+        self.lexer.re_lex_shell_word(closing);
+    }
+
     /// Returns the next non-trivia token without consuming it.
     ///
     /// Use [`peek2`] to get the next two tokens.
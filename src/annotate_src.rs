@@ -6,13 +6,13 @@ use std::fmt::Formatter;
 use crate::location::HasSrcLocation;
 use annotate_snippets::display_list::{DisplayList, FormatOptions};
 use annotate_snippets::snippet::{AnnotationType, Slice, Snippet, SourceAnnotation};
-use pyo3::PyErr;
 use pyo3::exceptions::PySyntaxError;
+use pyo3::prelude::*;
 
 pub(crate) fn to_syntax_err(filename: &str, code: &SourceCode, err: &ParseError) -> PyErr {
     let code_frame = CodeFrame::new(code, err);
     let msg = format!("{err} in {filename}:\n{code_frame}",);
-    PySyntaxError::new_err((
+    let py_err = PySyntaxError::new_err((
         msg,
         (
             filename.to_string(),
@@ -22,7 +22,19 @@ pub(crate) fn to_syntax_err(filename: &str, code: &SourceCode, err: &ParseError)
             code_frame.end_lineno(),
             code_frame.end_col_offset(),
         ),
-    ))
+    ));
+    if let ParseErrorType::ShellSyntax(error) = &err.error {
+        // The `(start, end, replacement)` edit that turns the bash construct into xonsh code,
+        // with character offsets so that it can be applied by slicing the Python `str`.
+        let offset = |at| code.slice(TextRange::up_to(at)).chars().count();
+        let fix = (
+            offset(err.location.start()),
+            offset(err.location.end()),
+            error.fix.as_str(),
+        );
+        let _ = Python::attach(|py| py_err.value(py).setattr("fix", fix));
+    }
+    py_err
 }
 
 pub(crate) struct CodeFrame<'a> {
@@ -0,0 +1,230 @@
+//! The `xonsh-rd-parser` command line interface.
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+
+use crate::lint::select_rules;
+use crate::parser::PyParser;
+
+const USAGE: &str = "\
+usage: xonsh-rd-parser <command> [<args>]
+
+commands:
+  transpile <file.xsh>... [-o <file.py>] [--no-source-map]
+      write the Python translation of each file next to it (or to `-o`, `-` for stdout),
+      along with a `<file.py>.map` source map
+  fmt <file.xsh>... [--check]
+      format the files in place, or with `--check` list the ones that would be reformatted
+  lint <file.xsh>... [--select <rules>]
+      report xonsh-specific issues, optionally only for a comma-separated list of rule codes,
+      code prefixes or names
+";
+
+/// Runs the command given in `args` (without the program name) and returns the exit code.
+pub(crate) fn run(py: Python<'_>, args: &[String]) -> PyResult<i32> {
+    match args.split_first() {
+        Some((command, rest)) if command == "transpile" => transpile(py, rest),
+        Some((command, rest)) if command == "fmt" => fmt(py, rest),
+        Some((command, rest)) if command == "lint" => lint(py, rest),
+        Some((command, _)) if command == "-h" || command == "--help" => {
+            std::io::stdout().write_all(USAGE.as_bytes())?;
+            Ok(0)
+        }
+        _ => usage_error("expected a command"),
+    }
+}
+
+fn usage_error(msg: &str) -> PyResult<i32> {
+    writeln!(std::io::stderr(), "error: {msg}\n\n{USAGE}")?;
+    Ok(2)
+}
+
+/// Reports a failure for the given file and returns the exit code for it.
+fn file_error(file: &Path, msg: impl std::fmt::Display) -> PyResult<i32> {
+    writeln!(std::io::stderr(), "{}: {msg}", file.display())?;
+    Ok(1)
+}
+
+fn read_source<'py>(py: Python<'py>, file: &Path) -> std::io::Result<Bound<'py, PyString>> {
+    let src = std::fs::read_to_string(file)?;
+    Ok(PyString::new(py, &src))
+}
+
+fn transpile(py: Python<'_>, args: &[String]) -> PyResult<i32> {
+    let mut files = Vec::new();
+    let mut output = None;
+    let mut source_map = true;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => match args.next() {
+                Some(value) => output = Some(PathBuf::from(value)),
+                None => return usage_error("`--output` expects a path"),
+            },
+            "--no-source-map" => source_map = false,
+            flag if flag.starts_with('-') && flag != "-" => {
+                return usage_error(&format!("unknown option `{flag}`"));
+            }
+            file => files.push(PathBuf::from(file)),
+        }
+    }
+    if files.is_empty() {
+        return usage_error("`transpile` expects at least one file");
+    }
+    if output.is_some() && files.len() > 1 {
+        return usage_error("`--output` can only be used with a single file");
+    }
+
+    let mut code = 0;
+    for file in &files {
+        let src = match read_source(py, file) {
+            Ok(src) => src,
+            Err(err) => {
+                code = file_error(file, err)?;
+                continue;
+            }
+        };
+        let file_name = file.to_string_lossy();
+        let transpiled = PyParser::new(src, Some(&file_name), None, false)?.transpile(py);
+        let (python, map) = match transpiled {
+            Ok(transpiled) => transpiled,
+            Err(err) => {
+                code = file_error(file, err)?;
+                continue;
+            }
+        };
+
+        let out = output.clone().unwrap_or_else(|| file.with_extension("py"));
+        if out == Path::new("-") {
+            std::io::stdout().write_all(python.as_bytes())?;
+            continue;
+        }
+        if &out == file {
+            code = file_error(file, "refusing to overwrite the input file")?;
+            continue;
+        }
+        if let Err(err) = std::fs::write(&out, python) {
+            code = file_error(&out, err)?;
+            continue;
+        }
+        if source_map {
+            let mut map_file = OsString::from(out.as_os_str());
+            map_file.push(".map");
+            if let Err(err) = std::fs::write(&map_file, map.to_json()) {
+                code = file_error(Path::new(&map_file), err)?;
+            }
+        }
+    }
+    Ok(code)
+}
+
+fn fmt(py: Python<'_>, args: &[String]) -> PyResult<i32> {
+    let mut files = Vec::new();
+    let mut check = false;
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            flag if flag.starts_with('-') => {
+                return usage_error(&format!("unknown option `{flag}`"));
+            }
+            file => files.push(PathBuf::from(file)),
+        }
+    }
+    if files.is_empty() {
+        return usage_error("`fmt` expects at least one file");
+    }
+
+    let mut code = 0;
+    for file in &files {
+        let src = match read_source(py, file) {
+            Ok(src) => src,
+            Err(err) => {
+                code = file_error(file, err)?;
+                continue;
+            }
+        };
+        let file_name = file.to_string_lossy();
+        let parser = PyParser::new(src.clone(), Some(&file_name), None, false)?;
+        let formatted = match parser.format(py) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                code = file_error(file, err)?;
+                continue;
+            }
+        };
+        if formatted == src.to_str()? {
+            continue;
+        }
+        if check {
+            writeln!(std::io::stdout(), "would reformat {}", file.display())?;
+            code = 1;
+        } else if let Err(err) = std::fs::write(file, formatted) {
+            code = file_error(file, err)?;
+        }
+    }
+    Ok(code)
+}
+
+fn lint(py: Python<'_>, args: &[String]) -> PyResult<i32> {
+    let mut files = Vec::new();
+    let mut select = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--select" => match args.next() {
+                Some(value) => select = Some(value.split(',').map(String::from).collect()),
+                None => return usage_error("`--select` expects a list of rules"),
+            },
+            flag if flag.starts_with('-') => {
+                return usage_error(&format!("unknown option `{flag}`"));
+            }
+            file => files.push(PathBuf::from(file)),
+        }
+    }
+    if files.is_empty() {
+        return usage_error("`lint` expects at least one file");
+    }
+    if let Some(select) = &select
+        && let Err(err) = select_rules(select)
+    {
+        return usage_error(&err);
+    }
+
+    let mut code = 0;
+    for file in &files {
+        let src = match read_source(py, file) {
+            Ok(src) => src,
+            Err(err) => {
+                code = file_error(file, err)?;
+                continue;
+            }
+        };
+        let file_name = file.to_string_lossy();
+        let parser = PyParser::new(src, Some(&file_name), None, false)?;
+        let diagnostics = match parser.lint(py, select.clone()) {
+            Ok(diagnostics) => diagnostics,
+            Err(err) => {
+                code = file_error(file, err)?;
+                continue;
+            }
+        };
+        for diagnostic in &diagnostics {
+            let (line, column) = diagnostic.location();
+            writeln!(
+                std::io::stdout(),
+                "{}:{line}:{}: {} {}",
+                file.display(),
+                column + 1,
+                diagnostic.code(),
+                diagnostic.message()
+            )?;
+            code = 1;
+        }
+    }
+    Ok(code)
+}
@@ -0,0 +1,510 @@
+//! An opinionated formatter for xonsh files.
+//!
+//! Statements are printed by the [generator](crate::generator), so the Python parts follow the
+//! style of ruff's code generator with double quotes, and commands are normalized: single spaces
+//! around pipes and redirects, no line continuations and quotes only where an argument needs
+//! them. Comments and blank lines are taken from the tokens and put back between the statements.
+//! `with!` suites and macro arguments are kept verbatim.
+//!
+//! Unlike black, long lines aren't wrapped. A statement, or the header of a compound statement,
+//! that is written over several lines is joined into one line only if it fits in
+//! [`LINE_LENGTH`] columns and has no comments inside, and is kept as it is written otherwise.
+use std::fmt;
+
+use ruff_python_ast::{self as ast, AtomicNodeIndex, Expr, ExprContext, Stmt, name::Name};
+use ruff_python_codegen::{Generator, Indentation};
+use ruff_python_parser::{ParseError, TokenKind};
+use ruff_source_file::{LineEnding, LineIndex, OneIndexed, SourceCode};
+use ruff_text_size::{Ranged, TextLen, TextRange, TextSize};
+
+use crate::generator::{XonshGenerator, replace_placeholder};
+
+/// Names of the statements standing in for the suites of a compound statement.
+const SUITE_PLACEHOLDER: &str = "__xonsh_formatter_suite_";
+
+/// The width that a statement written over several lines must fit in to be joined, like black's
+/// line length.
+const LINE_LENGTH: usize = 88;
+
+/// The width of an indentation level of the generated code.
+const INDENT_WIDTH: usize = 4;
+
+#[derive(Debug)]
+pub enum FormatError {
+    Parse(ParseError),
+    /// The formatted code doesn't parse to the same tree as the source.
+    Unstable,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Parse(err) => fmt::Display::fmt(err, f),
+            FormatError::Unstable => {
+                f.write_str("formatting would change the meaning of the code, this is a bug")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<ParseError> for FormatError {
+    fn from(err: ParseError) -> Self {
+        FormatError::Parse(err)
+    }
+}
+
+/// Formats the xonsh source code.
+pub fn format(source: &str) -> Result<String, FormatError> {
+    let parsed = ruff_python_parser::parse_module(source)?;
+    let comments = parsed
+        .tokens()
+        .iter()
+        .filter(|token| token.kind() == TokenKind::Comment)
+        .map(Ranged::range)
+        .collect();
+    let colons = parsed
+        .tokens()
+        .iter()
+        .filter(|token| token.kind() == TokenKind::Colon)
+        .map(Ranged::end)
+        .collect();
+    let line_index = LineIndex::from_source_text(source);
+
+    let mut formatter = Formatter {
+        source: SourceCode::new(source, &line_index),
+        generator: XonshGenerator::double_quoted(),
+        comments,
+        next_comment: 0,
+        colons,
+    };
+    let mut code = formatter.suite(parsed.suite(), source.text_len(), 0);
+    if !code.is_empty() {
+        code.push('\n');
+    }
+
+    let formatted = ruff_python_parser::parse_module(&code).map_err(|_| FormatError::Unstable)?;
+    if lowered(parsed.suite()) != lowered(formatted.suite()) {
+        return Err(FormatError::Unstable);
+    }
+    Ok(code)
+}
+
+struct Formatter<'a> {
+    source: SourceCode<'a, 'a>,
+    generator: XonshGenerator,
+    comments: Vec<TextRange>,
+    /// Comments are emitted in source order, those before this one have been emitted.
+    next_comment: usize,
+    /// The ends of the `:` tokens, to find where the header of a suite ends.
+    colons: Vec<TextSize>,
+}
+
+impl Formatter<'_> {
+    /// Formats a block of statements without indentation, followed by its dangling comments.
+    ///
+    /// `limit` is where the next block starts, comments before it that are indented like the
+    /// block belong to it.
+    fn suite(&mut self, stmts: &[Stmt], limit: TextSize, depth: usize) -> String {
+        let Some(first) = stmts.first() else {
+            return String::new();
+        };
+        let max_blank_lines = if depth == 0 { 2 } else { 1 };
+        let column = self.column(first.start());
+        let mut lines: Vec<String> = Vec::new();
+
+        for (idx, stmt) in stmts.iter().enumerate() {
+            let is_def = |stmt: &Stmt| matches!(stmt, Stmt::FunctionDef(_) | Stmt::ClassDef(_));
+            let mut required = match idx.checked_sub(1).map(|prev| &stmts[prev]) {
+                Some(prev) if is_def(prev) || is_def(stmt) => max_blank_lines,
+                _ => 0,
+            };
+
+            for comment in self.take_comments(stmt.start()) {
+                let blank_lines = self
+                    .blank_lines_before(comment.start())
+                    .min(max_blank_lines);
+                push_blank_lines(&mut lines, blank_lines.max(required));
+                lines.push(self.comment(comment));
+                required = 0;
+            }
+            let blank_lines = self.blank_lines_before(stmt.start()).min(max_blank_lines);
+            push_blank_lines(&mut lines, blank_lines.max(required));
+
+            let next = stmts.get(idx + 1).map_or(limit, Ranged::start);
+            lines.push(self.stmt(stmt, next, depth));
+        }
+
+        while let Some(&comment) = self.comments.get(self.next_comment)
+            && comment.start() < limit
+            && self.column(comment.start()) >= column
+        {
+            self.next_comment += 1;
+            let blank_lines = self
+                .blank_lines_before(comment.start())
+                .min(max_blank_lines);
+            push_blank_lines(&mut lines, blank_lines);
+            lines.push(self.comment(comment));
+        }
+        lines.join("\n")
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, limit: TextSize, depth: usize) -> String {
+        let is_macro = self.generator.with_macro(stmt).is_some();
+        let mut compound = stmt.clone();
+        let mut suites = Vec::new();
+        if !is_macro {
+            let clauses = clause_starts(stmt);
+            for (suite, start) in suites_mut(&mut compound).into_iter().zip(clauses) {
+                if !suite.is_empty() {
+                    let placeholder = placeholder_stmt(suites.len());
+                    suites.push((std::mem::replace(suite, vec![placeholder]), start));
+                }
+            }
+        }
+        if suites.is_empty() {
+            return self.simple_stmt(stmt, limit, depth, is_macro);
+        }
+
+        let headers: Vec<TextRange> = suites
+            .iter()
+            .map(|(suite, start)| self.header_range(*start, suite[0].start()))
+            .collect();
+        let mut code = self.generator.stmt(&compound);
+        for (idx, (suite, _)) in suites.iter().enumerate() {
+            let placeholder = suite_placeholder(idx);
+            // The first suite of a function is preceded by its decorators and its `def`, while
+            // the first case of a `match` is preceded by the `match` line.
+            let whole_prefix = idx == 0 && !matches!(stmt, Stmt::Match(_));
+            let header = Header {
+                range: headers[idx],
+                body: suite[0].start(),
+                whole_prefix,
+            };
+            self.header(&mut code, &header, &placeholder, depth);
+
+            let next = headers.get(idx + 1).map_or(limit, Ranged::start);
+            let text = self.suite(suite, next, depth + 1);
+            replace_placeholder(&mut code, &placeholder, &text);
+        }
+        code
+    }
+
+    /// Formats a simple statement or a `with!` macro along with its comments.
+    ///
+    /// Comments inside the statement can only stay where they are if it's kept as it is written,
+    /// while the one at the end of the last line, or of the first line of a `with!`, is put back
+    /// at the end of that line. The ones in a `with!` suite are part of it.
+    fn simple_stmt(
+        &mut self,
+        stmt: &Stmt,
+        limit: TextSize,
+        depth: usize,
+        is_macro: bool,
+    ) -> String {
+        let first_line = self.line(stmt.start());
+        let last_line = self.line(stmt.end().checked_sub(1.into()).unwrap_or_default());
+        let end = self.source.line_end_exclusive(last_line).min(limit);
+        let trailing_line = if is_macro { first_line } else { last_line };
+        let (trailing, inner): (Vec<_>, Vec<_>) = self
+            .take_comments(end)
+            .into_iter()
+            .partition(|comment| self.line(comment.start()) == trailing_line);
+
+        let mut code = self.generate_simple(stmt);
+        if !is_macro && first_line != last_line && (!inner.is_empty() || is_too_long(&code, depth))
+        {
+            code = self.verbatim(stmt.range(), "");
+        }
+        if let Some(&comment) = trailing.last() {
+            let line_end = if trailing_line == first_line {
+                code.find('\n').unwrap_or(code.len())
+            } else {
+                code.len()
+            };
+            code.insert_str(line_end, &format!("  {}", self.comment(comment)));
+        }
+        code
+    }
+
+    /// Returns the range of the header of a suite, from the start of its clause, or of its line
+    /// for a clause like `else:` that has no range, to its `:`.
+    fn header_range(&self, start: Option<TextSize>, body: TextSize) -> TextRange {
+        let colon = self.colons.partition_point(|&end| end <= body);
+        let end = self.colons[colon - 1];
+        let start = start.unwrap_or_else(|| {
+            let line_start = self.source.line_start(self.line(end));
+            let line = &self.source.text()[line_start.to_usize()..];
+            line_start + (line.text_len() - line.trim_start().text_len())
+        });
+        TextRange::new(start, end)
+    }
+
+    /// Puts the comments around the header of a suite into the generated `code`, where the
+    /// header is the line before the placeholder of its suite, or all the lines before it with
+    /// `whole_prefix`.
+    ///
+    /// The comments above a clause like `else:` stay above it, and the one after its `:` stays
+    /// at the end of its line. A header written over several lines is kept as it is if it has
+    /// comments inside or doesn't fit on one line.
+    fn header(&mut self, code: &mut String, header: &Header, placeholder: &str, depth: usize) {
+        let line_end = self
+            .source
+            .line_end_exclusive(self.line(header.range.end()));
+        let comments = self.take_comments(line_end.min(header.body));
+
+        let placeholder_start = code.find(placeholder).expect("the suite has a placeholder");
+        let end = code[..placeholder_start].rfind('\n').unwrap_or_default();
+        let start = if header.whole_prefix {
+            0
+        } else {
+            code[..end].rfind('\n').map_or(0, |newline| newline + 1)
+        };
+        let generated = &code[start..end];
+        let indent = &generated[..generated.len() - generated.trim_start().len()];
+
+        let mut text = generated.trim_start().to_string();
+        let is_multiline = self.line(header.range.start()) != self.line(header.range.end());
+        let has_inner_comments = comments
+            .iter()
+            .any(|comment| header.range.contains(comment.start()));
+        if is_multiline && (has_inner_comments || is_too_long(generated, depth)) {
+            text = self.verbatim(header.range, indent);
+        }
+        if let Some(&comment) = comments
+            .iter()
+            .find(|comment| comment.start() >= header.range.end())
+        {
+            text.push_str(&format!("  {}", self.comment(comment)));
+        }
+
+        let mut lines: Vec<String> = comments
+            .iter()
+            .filter(|comment| comment.start() < header.range.start())
+            .map(|&comment| format!("{indent}{}", self.comment(comment)))
+            .collect();
+        lines.push(format!("{indent}{text}"));
+        code.replace_range(start..end, &lines.join("\n"));
+    }
+
+    /// Generates a simple statement, keeping commands that were written without `![...]` bare.
+    fn generate_simple(&self, stmt: &Stmt) -> String {
+        let code = self.generator.stmt(stmt);
+        let src = &self.source.text()[stmt.range()];
+        if matches!(stmt, Stmt::Expr(_))
+            && code.starts_with("![")
+            && code.ends_with(']')
+            && !src.starts_with("![")
+        {
+            let bare = &code[2..code.len() - 1];
+            let same = ruff_python_parser::parse_module(bare)
+                .is_ok_and(|parsed| lowered(parsed.suite()) == lowered(std::slice::from_ref(stmt)));
+            if same {
+                return bare.to_string();
+            }
+        }
+        code
+    }
+
+    /// Returns the source code in the range, with the lines after the first one moved along with
+    /// the first one to the given indentation.
+    fn verbatim(&self, range: TextRange, indent: &str) -> String {
+        let column = self.column(range.start());
+        let mut lines = self.source.slice(range).lines();
+        let first = lines.next().unwrap_or_default().to_string();
+        std::iter::once(first)
+            .chain(lines.map(|line| {
+                let dedent = line.len() - line.trim_start().len();
+                match &line[dedent.min(column)..] {
+                    "" => String::new(),
+                    line => format!("{indent}{line}"),
+                }
+            }))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Takes the comments that start before `offset`.
+    fn take_comments(&mut self, offset: TextSize) -> Vec<TextRange> {
+        let start = self.next_comment;
+        while self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.start() < offset)
+        {
+            self.next_comment += 1;
+        }
+        self.comments[start..self.next_comment].to_vec()
+    }
+
+    /// Returns the comment with a space after the `#`, like black.
+    fn comment(&self, range: TextRange) -> String {
+        let comment = self.source.slice(range).trim_end();
+        let content = &comment[1..];
+        if content.is_empty() || content.starts_with([' ', '!', ':', '#']) {
+            comment.to_string()
+        } else {
+            format!("# {content}")
+        }
+    }
+
+    fn line(&self, offset: TextSize) -> OneIndexed {
+        self.source.line_column(offset).line
+    }
+
+    fn column(&self, offset: TextSize) -> usize {
+        self.source.line_column(offset).column.to_zero_indexed()
+    }
+
+    /// Counts the empty lines right above the line of `offset`.
+    fn blank_lines_before(&self, offset: TextSize) -> usize {
+        let line_start = self.source.line_start(self.line(offset));
+        self.source.text()[..line_start.to_usize()]
+            .lines()
+            .rev()
+            .take_while(|line| line.trim().is_empty())
+            .count()
+    }
+}
+
+/// The header of a suite in the source, and where its body starts.
+struct Header {
+    range: TextRange,
+    body: TextSize,
+    /// Whether all the generated lines before the suite belong to the header.
+    whole_prefix: bool,
+}
+
+/// Returns `true` if a line of the generated code doesn't fit in [`LINE_LENGTH`] columns at the
+/// given depth.
+fn is_too_long(code: &str, depth: usize) -> bool {
+    code.lines()
+        .any(|line| depth * INDENT_WIDTH + line.chars().count() > LINE_LENGTH)
+}
+
+fn push_blank_lines(lines: &mut Vec<String>, count: usize) {
+    if !lines.is_empty() {
+        lines.extend(std::iter::repeat_n(String::new(), count));
+    }
+}
+
+fn suite_placeholder(idx: usize) -> String {
+    format!("{SUITE_PLACEHOLDER}{idx}__")
+}
+
+fn placeholder_stmt(idx: usize) -> Stmt {
+    let name = Expr::Name(ast::ExprName {
+        id: Name::new(suite_placeholder(idx)),
+        ctx: ExprContext::Load,
+        range: TextRange::default(),
+        node_index: AtomicNodeIndex::NONE,
+    });
+    Stmt::Expr(ast::StmtExpr {
+        value: Box::new(name),
+        range: TextRange::default(),
+        node_index: AtomicNodeIndex::NONE,
+    })
+}
+
+/// Returns the suites of a compound statement in source order.
+fn suites_mut(stmt: &mut Stmt) -> Vec<&mut Vec<Stmt>> {
+    match stmt {
+        Stmt::FunctionDef(def) => vec![&mut def.body],
+        Stmt::ClassDef(class) => vec![&mut class.body],
+        Stmt::With(with) => vec![&mut with.body],
+        Stmt::For(for_) => vec![&mut for_.body, &mut for_.orelse],
+        Stmt::While(while_) => vec![&mut while_.body, &mut while_.orelse],
+        Stmt::If(if_) => std::iter::once(&mut if_.body)
+            .chain(
+                if_.elif_else_clauses
+                    .iter_mut()
+                    .map(|clause| &mut clause.body),
+            )
+            .collect(),
+        Stmt::Try(try_) => std::iter::once(&mut try_.body)
+            .chain(try_.handlers.iter_mut().map(|handler| match handler {
+                ast::ExceptHandler::ExceptHandler(handler) => &mut handler.body,
+            }))
+            .chain([&mut try_.orelse, &mut try_.finalbody])
+            .collect(),
+        Stmt::Match(match_) => match_.cases.iter_mut().map(|case| &mut case.body).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns where the clause of each suite of [`suites_mut`] starts, like an `elif` or a `case`,
+/// or `None` for an `else:` or `finally:`, which has no range.
+fn clause_starts(stmt: &Stmt) -> Vec<Option<TextSize>> {
+    let start = Some(stmt.start());
+    match stmt {
+        Stmt::FunctionDef(_) | Stmt::ClassDef(_) | Stmt::With(_) => vec![start],
+        Stmt::For(_) | Stmt::While(_) => vec![start, None],
+        Stmt::If(if_) => std::iter::once(start)
+            .chain(
+                if_.elif_else_clauses
+                    .iter()
+                    .map(|clause| Some(clause.start())),
+            )
+            .collect(),
+        Stmt::Try(try_) => std::iter::once(start)
+            .chain(try_.handlers.iter().map(|handler| Some(handler.start())))
+            .chain([None, None])
+            .collect(),
+        Stmt::Match(match_) => match_.cases.iter().map(|case| Some(case.start())).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The lowered Python code of the statements, to check that formatting kept the tree intact.
+fn lowered(body: &[Stmt]) -> Vec<String> {
+    let indentation = Indentation::default();
+    body.iter()
+        .map(|stmt| Generator::new(&indentation, LineEnding::Lf).stmt(stmt))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format;
+
+    #[test]
+    fn test_format_commands() {
+        assert_eq!(
+            format("![ls   -l|grep  'wakka'>out.txt]\nx=$(ls  \\\n  tmp)\nls  tmp-dir &\n")
+                .unwrap(),
+            "![ls -l | grep wakka > out.txt]\nx = $(ls tmp)\nls tmp-dir &\n"
+        );
+    }
+
+    #[test]
+    fn test_format_comments() {
+        let src = "#!/usr/bin/env xonsh\nimport os\n# setup\ndef f( x ):  #trailing\n    #inside\n    return x\n\n\n\n\nls tmp  # list\n";
+        assert_eq!(
+            format(src).unwrap(),
+            "#!/usr/bin/env xonsh\nimport os\n\n\n# setup\ndef f(x):  # trailing\n    # inside\n    return x\n\n\nls tmp  # list\n"
+        );
+    }
+
+    #[test]
+    fn test_format_multiline_statements() {
+        let long = format!(
+            "x = [\n    {},\n]\n",
+            "1, ".repeat(30).trim_end_matches(", ")
+        );
+        assert_eq!(format(&long).unwrap(), long);
+        let src = "if x:\n    y = {\n        1: 2,  # two\n      }\nelse:\n    pass\n";
+        assert_eq!(format(src).unwrap(), src);
+        let src = "for x in (a,  # first\n          b):\n    pass\n# dedented\nelse:\n    pass\n";
+        assert_eq!(format(src).unwrap(), src);
+    }
+
+    #[test]
+    fn test_format_keeps_with_macro() {
+        let src = "if True:\n  with! x:  # macro\n      some   text  #kept\n        more\n";
+        assert_eq!(
+            format(src).unwrap(),
+            "if True:\n    with! x:  # macro\n        some   text  #kept\n          more\n"
+        );
+    }
+}
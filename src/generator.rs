@@ -0,0 +1,890 @@
+//! Generates xonsh source code from the AST of a xonsh file.
+//!
+//! The parser lowers xonsh syntax to calls on `__xonsh__` (e.g. `$(ls)` becomes
+//! `__xonsh__.cmd('ls').out()`). The generator recognizes those calls and prints them back in
+//! their xonsh form, leaving the Python parts to [`ruff_python_codegen`]. Calls that can't be
+//! printed back losslessly are kept in their lowered form, which parses to the same tree.
+use std::cell::RefCell;
+
+use ruff_python_ast::name::Name;
+use ruff_python_ast::visitor::transformer::{Transformer, walk_expr, walk_stmt};
+use ruff_python_ast::{self as ast, AtomicNodeIndex, Expr, ExprContext, Stmt};
+use ruff_python_codegen::{Generator, Indentation, Stylist};
+use ruff_source_file::LineEnding;
+use ruff_text_size::TextRange;
+
+const INDENT: &str = "    ";
+
+/// Names used in place of the xonsh parts of an expression while ruff generates the rest.
+const PLACEHOLDER: &str = "__xonsh_generator_placeholder_";
+
+/// The names that make a word followed by `>` a redirect, see `parse_cmd_group`.
+const REDIR_NAMES: &[&str] = &["o", "out", "e", "err", "a", "all"];
+
+/// Generates the xonsh source code for the given module body.
+///
+/// Parsing the generated code gives back an equivalent tree.
+pub fn generate(body: &[Stmt]) -> String {
+    let generator = XonshGenerator::default();
+    let mut code = String::new();
+    for stmt in body {
+        code.push_str(&generator.stmt(stmt));
+        code.push('\n');
+    }
+    code
+}
+
+/// Generates the xonsh source code for a single expression.
+pub fn generate_expr(expr: &Expr) -> String {
+    XonshGenerator::default().expr(expr)
+}
+
+#[derive(Default)]
+pub(crate) struct XonshGenerator {
+    indentation: Indentation,
+    /// Overrides the quotes and indentation of the generated Python code.
+    stylist: Option<Stylist<'static>>,
+}
+
+impl XonshGenerator {
+    /// A generator that quotes strings with double quotes, like black.
+    pub(crate) fn double_quoted() -> Self {
+        const SOURCE: &str = "\"\"\n";
+        let parsed = ruff_python_parser::parse_module(SOURCE).expect("valid Python source");
+        Self {
+            stylist: Some(Stylist::from_tokens(parsed.tokens(), SOURCE)),
+            ..Self::default()
+        }
+    }
+
+    fn python(&self) -> Generator<'_> {
+        match &self.stylist {
+            Some(stylist) => Generator::from(stylist),
+            None => Generator::new(&self.indentation, LineEnding::Lf),
+        }
+    }
+
+    pub(crate) fn stmt(&self, stmt: &Stmt) -> String {
+        let placeholders = Placeholders::new(self);
+        let mut stmt = stmt.clone();
+        placeholders.visit_stmt(&mut stmt);
+        let code = self.python().stmt(&stmt);
+        placeholders.fill(code)
+    }
+
+    fn expr(&self, expr: &Expr) -> String {
+        if let Some(code) = self.xonsh_expr(expr) {
+            return code;
+        }
+        let placeholders = Placeholders::new(self);
+        let mut expr = expr.clone();
+        placeholders.visit_expr(&mut expr);
+        let code = self.python().expr(&expr);
+        placeholders.fill(code)
+    }
+
+    /// Generates `expr` so that it can be followed by a postfix operator like `?` or `!(`.
+    fn primary(&self, expr: &Expr) -> String {
+        let code = self.expr(expr);
+        let is_primary = matches!(
+            expr,
+            Expr::Name(_) | Expr::Attribute(_) | Expr::Call(_) | Expr::Subscript(_)
+        );
+        if is_primary && !code.ends_with('?') {
+            code
+        } else {
+            format!("({code})")
+        }
+    }
+
+    /// Returns the xonsh form of an expression in Python mode, if it is a lowered xonsh one.
+    fn xonsh_expr(&self, expr: &Expr) -> Option<String> {
+        if let Some(slice) = env_slice(expr) {
+            return Some(match env_name(slice) {
+                Some(name) => format!("${name}"),
+                None => format!("${{{}}}", self.expr(slice)),
+            });
+        }
+        if let Some(code) = self.subproc(expr) {
+            return Some(code);
+        }
+        if let Some(code) = self.special_string(expr) {
+            return Some(code);
+        }
+        if let Expr::Starred(starred) = expr {
+            return self.pattern_invoke(&starred.value);
+        }
+        if let Some(code) = self.pattern_invoke(expr) {
+            return Some(code);
+        }
+        if let Some([obj]) = xonsh_call(expr, "help") {
+            return Some(format!("{}?", self.primary(obj)));
+        }
+        if let Some([obj]) = xonsh_call(expr, "superhelp") {
+            return Some(format!("{}??", self.primary(obj)));
+        }
+        if let Some([func, Expr::Tuple(args), _, _]) = xonsh_call(expr, "call_macro") {
+            let args = args
+                .elts
+                .iter()
+                .map(single_string)
+                .collect::<Option<Vec<_>>>()?;
+            return Some(format!("{}!({})", self.primary(func), args.join(", ")));
+        }
+        None
+    }
+
+    /// Prints `p"..."`, `` `...` `` and `` g`...` `` strings, and `` p`...` `` or `` pg`...` ``
+    /// ones that return paths.
+    fn special_string(&self, expr: &Expr) -> Option<String> {
+        if let Some([path]) = xonsh_call(expr, "path_literal") {
+            let is_single = match path {
+                Expr::StringLiteral(string) => !string.value.is_implicit_concatenated(),
+                Expr::FString(string) => !string.value.is_implicit_concatenated(),
+                _ => false,
+            };
+            let code = self.expr(path);
+            let quoted = code.trim_start_matches('f').starts_with(['\'', '"']);
+            return (is_single && quoted).then(|| format!("p{code}"));
+        }
+        let (receiver, method, arguments) = method_call(expr)?;
+        let paths = match &*arguments.keywords {
+            [] => false,
+            [keyword] => match (
+                keyword.arg.as_ref().map(ast::Identifier::as_str),
+                &keyword.value,
+            ) {
+                (Some("paths"), Expr::BooleanLiteral(paths)) if paths.value => true,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let prefix = match (method, paths) {
+            ("regex", false) => "",
+            ("regex", true) => "p",
+            ("glob", false) => "g",
+            ("glob", true) => "pg",
+            _ => return None,
+        };
+        match xonsh_call(receiver, "Pattern") {
+            Some([pattern]) if arguments.args.is_empty() => backtick_string(prefix, pattern),
+            _ => None,
+        }
+    }
+
+    /// Prints a subprocess capture like `$(ls | grep x > out.txt)`.
+    fn subproc(&self, expr: &Expr) -> Option<String> {
+        let mut methods = Vec::new();
+        let body = self.cmd_chain(expr, Some(ast::BoolOp::Or), true, &mut methods)?;
+        let (&method, tested) = methods.split_last()?;
+        if tested.iter().any(|&tested| tested != tested_method(method)) {
+            return None;
+        }
+        let (open, close) = match method {
+            "out" => ("$(", ")"),
+            "run" => ("$[", "]"),
+            "hide" => ("![", "]"),
+            "obj" => ("!(", ")"),
+            "inject" => ("@$(", ")"),
+            _ => return None,
+        };
+        Some(format!("{open}{body}{close}"))
+    }
+
+    /// Prints the pipelines of a subprocess capture, chained with `&&` and `||`, and collects the
+    /// method of each one. `loosest` is the loosest operator that can be printed without
+    /// parentheses, as commands can't be grouped.
+    fn cmd_chain<'a>(
+        &self,
+        expr: &'a Expr,
+        loosest: Option<ast::BoolOp>,
+        is_last: bool,
+        methods: &mut Vec<&'a str>,
+    ) -> Option<String> {
+        if let Expr::BoolOp(bool_op) = expr {
+            let (separator, inner) = match (bool_op.op, loosest?) {
+                (ast::BoolOp::Or, ast::BoolOp::Or) => (" || ", Some(ast::BoolOp::And)),
+                (ast::BoolOp::Or, ast::BoolOp::And) => return None,
+                (ast::BoolOp::And, _) => (" && ", None),
+            };
+            let last = bool_op.values.len() - 1;
+            let values = bool_op
+                .values
+                .iter()
+                .enumerate()
+                .map(|(idx, value)| self.cmd_chain(value, inner, is_last && idx == last, methods))
+                .collect::<Option<Vec<_>>>()?;
+            return Some(values.join(separator));
+        }
+
+        let (method, groups) = subproc_groups(expr)?;
+        methods.push(method);
+        self.pipeline(&groups, is_last)
+    }
+
+    /// Prints the commands of a pipeline with the pipes between them.
+    fn pipeline(&self, groups: &[&ast::Arguments], is_last: bool) -> Option<String> {
+        let last = groups.len() - 1;
+        let mut code = String::new();
+        for (idx, group) in groups.iter().enumerate() {
+            let pipe = pipe_operator(group)?;
+            if idx > 0 {
+                code.push_str(pipe);
+            } else if pipe != " | " {
+                return None;
+            }
+            code.push_str(&self.cmd_group(group, is_last && idx == last)?);
+        }
+        Some(code)
+    }
+
+    /// Prints `<(cmd)` and `>(cmd)`.
+    fn process_substitution(&self, expr: &Expr) -> Option<String> {
+        let [mode, pipeline] = xonsh_call(expr, "process_substitution")? else {
+            return None;
+        };
+        let mode = single_string(mode).filter(|mode| matches!(*mode, "<" | ">"))?;
+        let groups = pipeline_groups(pipeline)?;
+        Some(format!("{mode}({})", self.pipeline(&groups, false)?))
+    }
+
+    /// Prints the environment variables, arguments, redirects and `&` of a single command.
+    fn cmd_group(&self, group: &ast::Arguments, is_last: bool) -> Option<String> {
+        let mut modifiers = Vec::new();
+        let mut env = Vec::new();
+        let mut words: Vec<String> = Vec::with_capacity(group.args.len());
+        for arg in &group.args {
+            let mut word = self.proc_arg(arg)?;
+            // Python would concatenate adjacent strings into one.
+            if let Some(prev) = words.last()
+                && prev.ends_with(['\'', '"', '`'])
+                && starts_string(&word)
+            {
+                if is_string_atom(arg) {
+                    word = format!("({word})");
+                } else {
+                    return None;
+                }
+            }
+            words.push(word);
+        }
+
+        let mut redirects = Vec::new();
+        let mut background = false;
+        for keyword in &group.keywords {
+            match (
+                keyword.arg.as_ref().map(ast::Identifier::as_str),
+                &keyword.value,
+            ) {
+                (Some("redirects"), Expr::Dict(dict)) => {
+                    for item in &dict.items {
+                        let key = single_string(item.key.as_ref()?)?;
+                        match single_string(&item.value) {
+                            Some(fd) if fd.starts_with('&') => redirects.push(format!("{key}{fd}")),
+                            _ => redirects.push(format!("{key} {}", self.proc_arg(&item.value)?)),
+                        }
+                    }
+                }
+                (Some("env"), Expr::Dict(dict)) => {
+                    for item in &dict.items {
+                        let name = single_string(item.key.as_ref()?)?;
+                        let value = match single_string(&item.value) {
+                            Some("") => String::new(),
+                            _ => self.proc_arg(&item.value)?,
+                        };
+                        env.push(format!("{name}={value}"));
+                    }
+                }
+                (Some("modifiers"), Expr::List(list)) => {
+                    for elt in &list.elts {
+                        modifiers.push(format!("@{}", single_string(elt)?));
+                    }
+                }
+                (Some("bg"), Expr::BooleanLiteral(bg)) if bg.value && is_last => background = true,
+                // Printed by `pipe_operator`.
+                (Some("stream"), _) => {}
+                _ => return None,
+            }
+        }
+
+        // `o > file` would be read as the `o>` redirect.
+        if !redirects.is_empty()
+            && let (Some(word), Some(arg)) = (words.last_mut(), group.args.last())
+            && (REDIR_NAMES.contains(&word.as_str()) || word.bytes().all(|c| c.is_ascii_digit()))
+        {
+            *word = self.python().expr(arg);
+        }
+
+        words.extend(redirects);
+        if background {
+            words.push("&".to_string());
+        }
+        modifiers.extend(env);
+        modifiers.extend(words);
+        Some(modifiers.join(" "))
+    }
+
+    /// Prints a single argument of a command, joining the parts of `a$X@(y)` without whitespace.
+    fn proc_arg(&self, arg: &Expr) -> Option<String> {
+        if let Expr::Starred(starred) = arg {
+            return self.pattern_invoke(&starred.value);
+        }
+
+        let mut parts = Vec::new();
+        let mut expr = arg;
+        while let Expr::BinOp(bin_op) = expr
+            && bin_op.op == ast::Operator::Add
+        {
+            parts.push(bin_op.right.as_ref());
+            expr = &bin_op.left;
+        }
+        parts.push(expr);
+        parts.reverse();
+
+        let all_strings = parts.iter().all(|p| matches!(p, Expr::StringLiteral(_)));
+        if parts.len() > 1
+            && !all_strings
+            && let Some(code) = self.adjacent_parts(&parts)
+        {
+            return Some(code);
+        }
+        Some(self.proc_arg_part(arg, None))
+    }
+
+    fn adjacent_parts(&self, parts: &[&Expr]) -> Option<String> {
+        let mut code = String::new();
+        let mut prev: Option<&Expr> = None;
+        for part in parts {
+            let part_code = self.proc_arg_part(part, Some(&code));
+            if let Some(prev) = prev {
+                let fits = if part_code.starts_with(['<', '>']) {
+                    // `a<(cmd)` would be read as a redirect.
+                    false
+                } else if matches!(prev, Expr::StringLiteral(_)) {
+                    // A word swallows everything up to the next `$`, `@`, `!(` or `![`.
+                    part_code.starts_with(['$', '@', '!'])
+                } else if code.ends_with(['\'', '"', '`']) {
+                    !starts_string(&part_code)
+                } else {
+                    !(code.ends_with(is_identifier_char)
+                        && part_code.starts_with(is_identifier_char))
+                };
+                if !fits {
+                    return None;
+                }
+            }
+            code.push_str(&part_code);
+            prev = Some(part);
+        }
+        Some(code)
+    }
+
+    /// Prints a part of a command argument. Expressions without a xonsh form are parenthesized.
+    fn proc_arg_part(&self, part: &Expr, prev: Option<&str>) -> String {
+        if let Some(value) = single_string(part) {
+            let after_name = prev.is_some_and(|prev| prev.ends_with(is_identifier_char));
+            if is_bare_word(value) && !(after_name && value.starts_with(is_identifier_char)) {
+                return value.to_string();
+            }
+            return self.python().expr(part);
+        }
+        if env_slice(part).is_some() {
+            return self.expr(part);
+        }
+        if let Some(code) = self.subproc(part) {
+            return code;
+        }
+        if let Some([expr]) = xonsh_call(part, "list_of_strs_or_callables") {
+            return format!("@({})", self.expr(expr));
+        }
+        if let Some(code) = self.process_substitution(part) {
+            return code;
+        }
+        if let Some(code) = self.special_string(part) {
+            return code;
+        }
+        if let Expr::List(list) = part
+            && let Some(code) = brace_group(&list.elts)
+        {
+            return code;
+        }
+        let code = self.expr(part);
+        if is_string_atom(part) {
+            code
+        } else {
+            format!("({code})")
+        }
+    }
+
+    /// Prints a ``@name`pattern` `` search path interpolation.
+    fn pattern_invoke(&self, expr: &Expr) -> Option<String> {
+        let (receiver, "invoke", arguments) = method_call(expr)? else {
+            return None;
+        };
+        let ([Expr::Name(func)], [pattern]) = (&*arguments.args, xonsh_call(receiver, "Pattern")?)
+        else {
+            return None;
+        };
+        if !arguments.keywords.is_empty() || !matches!(pattern, Expr::StringLiteral(_)) {
+            return None;
+        }
+        let pattern = backtick_string("", pattern).unwrap_or_else(|| self.python().expr(pattern));
+        Some(format!("@{}{pattern}", func.id))
+    }
+
+    /// Prints a `with!` statement, whose body is kept verbatim.
+    pub(crate) fn with_macro(&self, stmt: &Stmt) -> Option<String> {
+        let Stmt::With(with) = stmt else {
+            return None;
+        };
+        if with.is_async {
+            return None;
+        }
+
+        let mut body = None;
+        let mut items = Vec::with_capacity(with.items.len());
+        for item in &with.items {
+            let Some([context, suite, _, _]) = xonsh_call(&item.context_expr, "enter_macro") else {
+                return None;
+            };
+            let suite = single_string(suite)?;
+            if body.is_some_and(|body| body != suite) {
+                return None;
+            }
+            body = Some(suite);
+
+            let mut code = self.expr(context);
+            if let Some(vars) = &item.optional_vars {
+                code.push_str(" as ");
+                code.push_str(&self.expr(vars));
+            }
+            items.push(code);
+        }
+
+        let mut code = format!("with! {}:", items.join(", "));
+        for line in body?.split('\n') {
+            code.push('\n');
+            if !line.trim().is_empty() {
+                code.push_str(INDENT);
+            }
+            code.push_str(line);
+        }
+        Some(code)
+    }
+}
+
+/// Replaces the xonsh parts of a tree with placeholder names and keeps their xonsh code.
+struct Placeholders<'a> {
+    generator: &'a XonshGenerator,
+    code: RefCell<Vec<String>>,
+}
+
+impl<'a> Placeholders<'a> {
+    fn new(generator: &'a XonshGenerator) -> Self {
+        Self {
+            generator,
+            code: RefCell::default(),
+        }
+    }
+
+    fn push(&self, code: String) -> Expr {
+        let mut codes = self.code.borrow_mut();
+        let name = placeholder(codes.len());
+        codes.push(code);
+        Expr::Name(ast::ExprName {
+            id: Name::new(name),
+            ctx: ExprContext::Load,
+            range: TextRange::default(),
+            node_index: AtomicNodeIndex::NONE,
+        })
+    }
+
+    /// Substitutes the placeholders in the generated `code`.
+    fn fill(self, mut code: String) -> String {
+        for (idx, xonsh) in self.code.into_inner().into_iter().enumerate().rev() {
+            replace_placeholder(&mut code, &placeholder(idx), &xonsh);
+        }
+        code
+    }
+}
+
+/// Replaces the placeholder `name` in `code` with `text`.
+///
+/// Multi-line text (e.g. a `with!` block) is indented like the line it replaces.
+pub(crate) fn replace_placeholder(code: &mut String, name: &str, text: &str) {
+    let Some(pos) = code.find(name) else {
+        return;
+    };
+    let line_start = code[..pos].rfind('\n').map_or(0, |idx| idx + 1);
+    let indent = &code[line_start..pos];
+    let text = if indent.is_empty() || !indent.trim().is_empty() {
+        text.to_string()
+    } else {
+        text.split('\n')
+            .enumerate()
+            .map(|(idx, line)| {
+                if idx == 0 || line.trim().is_empty() {
+                    line.to_string()
+                } else {
+                    format!("{indent}{line}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    code.replace_range(pos..pos + name.len(), &text);
+}
+
+impl Transformer for Placeholders<'_> {
+    fn visit_stmt(&self, stmt: &mut Stmt) {
+        match self.generator.with_macro(stmt) {
+            Some(code) => {
+                *stmt = Stmt::Expr(ast::StmtExpr {
+                    value: Box::new(self.push(code)),
+                    range: TextRange::default(),
+                    node_index: AtomicNodeIndex::NONE,
+                });
+            }
+            None => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&self, expr: &mut Expr) {
+        match self.generator.xonsh_expr(expr) {
+            Some(code) => *expr = self.push(code),
+            None => walk_expr(self, expr),
+        }
+    }
+}
+
+fn placeholder(idx: usize) -> String {
+    format!("{PLACEHOLDER}{idx}__")
+}
+
+/// Returns the pipe before a command, which is `|&` or `e|` when its `stream` keyword pipes the
+/// standard error of the previous one.
+fn pipe_operator(group: &ast::Arguments) -> Option<&'static str> {
+    match group
+        .find_keyword("stream")
+        .map(|keyword| single_string(&keyword.value))
+    {
+        None => Some(" | "),
+        Some(Some("all")) => Some(" |& "),
+        Some(Some("err")) => Some(" e| "),
+        Some(_) => None,
+    }
+}
+
+/// The method of the pipelines before the last one in a chain like `$(a && b)`, which are only
+/// tested for success, see `parse_pipeline`.
+fn tested_method(method: &str) -> &'static str {
+    if matches!(method, "hide" | "run") {
+        "hide"
+    } else {
+        "obj"
+    }
+}
+
+/// Splits a lowered subprocess capture into its method (`out`, `run`, `hide`, `obj` or `inject`)
+/// and the arguments of each command in the pipeline.
+pub(crate) fn subproc_groups(expr: &Expr) -> Option<(&str, Vec<&ast::Arguments>)> {
+    let (receiver, method, arguments) = method_call(expr)?;
+    if !matches!(method, "out" | "run" | "hide" | "obj" | "inject") || !is_empty(arguments) {
+        return None;
+    }
+    Some((method, pipeline_groups(receiver)?))
+}
+
+/// Splits a `__xonsh__.cmd(...).pipe(...)` call chain into the arguments of each command.
+pub(crate) fn pipeline_groups(mut receiver: &Expr) -> Option<Vec<&ast::Arguments>> {
+    let mut groups = Vec::new();
+    loop {
+        let Expr::Call(call) = receiver else {
+            return None;
+        };
+        if xonsh_attr(&call.func) == Some("cmd") {
+            groups.push(&call.arguments);
+            break;
+        }
+        let Expr::Attribute(attr) = call.func.as_ref() else {
+            return None;
+        };
+        if attr.attr.as_str() != "pipe" {
+            return None;
+        }
+        groups.push(&call.arguments);
+        receiver = &attr.value;
+    }
+    groups.reverse();
+    Some(groups)
+}
+
+/// Returns the attribute name if `expr` is `__xonsh__.<name>`.
+fn xonsh_attr(expr: &Expr) -> Option<&str> {
+    let Expr::Attribute(attr) = expr else {
+        return None;
+    };
+    let Expr::Name(name) = attr.value.as_ref() else {
+        return None;
+    };
+    (name.id.as_str() == "__xonsh__").then(|| attr.attr.as_str())
+}
+
+/// Returns the positional arguments of a `__xonsh__.<name>(...)` call.
+pub(crate) fn xonsh_call<'a>(expr: &'a Expr, name: &str) -> Option<&'a [Expr]> {
+    let Expr::Call(call) = expr else {
+        return None;
+    };
+    (xonsh_attr(&call.func) == Some(name) && call.arguments.keywords.is_empty())
+        .then_some(&*call.arguments.args)
+}
+
+/// Splits a `receiver.method(...)` call.
+pub(crate) fn method_call(expr: &Expr) -> Option<(&Expr, &str, &ast::Arguments)> {
+    let Expr::Call(call) = expr else {
+        return None;
+    };
+    let Expr::Attribute(attr) = call.func.as_ref() else {
+        return None;
+    };
+    Some((&attr.value, attr.attr.as_str(), &call.arguments))
+}
+
+pub(crate) fn is_empty(arguments: &ast::Arguments) -> bool {
+    arguments.args.is_empty() && arguments.keywords.is_empty()
+}
+
+/// Returns the key of a `__xonsh__.env[...]` lookup.
+pub(crate) fn env_slice(expr: &Expr) -> Option<&Expr> {
+    let Expr::Subscript(subscript) = expr else {
+        return None;
+    };
+    (xonsh_attr(&subscript.value) == Some("env")).then_some(subscript.slice.as_ref())
+}
+
+/// Returns the name for the `$NAME` form of an environment variable lookup.
+fn env_name(slice: &Expr) -> Option<&str> {
+    single_string(slice).filter(|name| {
+        name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(is_identifier_char)
+    })
+}
+
+pub(crate) fn single_string(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::StringLiteral(string) if !string.value.is_implicit_concatenated() => {
+            Some(string.value.to_str())
+        }
+        _ => None,
+    }
+}
+
+/// Prints a backtick string, if `pattern` can be written as one.
+fn backtick_string(prefix: &str, pattern: &Expr) -> Option<String> {
+    let value = single_string(pattern)?;
+    if value.contains(['`', '\n', '\r']) {
+        None
+    } else if !value.contains('\\') {
+        Some(format!("{prefix}`{value}`"))
+    } else if !value.ends_with('\\') {
+        Some(format!("{prefix}r`{value}`"))
+    } else {
+        None
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Whether the code starts with a string token, which Python concatenates with a previous one.
+fn starts_string(code: &str) -> bool {
+    code.trim_start_matches(|c: char| c.is_ascii_alphabetic())
+        .starts_with(['\'', '"', '`'])
+}
+
+/// Whether the argument is printed as a single string token.
+fn is_string_atom(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::StringLiteral(_) | Expr::FString(_) | Expr::TString(_) | Expr::BytesLiteral(_)
+    ) || xonsh_call(expr, "path_literal").is_some()
+        || method_call(expr).is_some_and(|(receiver, ..)| xonsh_call(receiver, "Pattern").is_some())
+}
+
+/// Prints the alternatives of an expanded brace group like `file.{txt,bak}`, if they share their
+/// start and end and can be written without quotes.
+fn brace_group(elts: &[Expr]) -> Option<String> {
+    let words = elts.iter().map(single_string).collect::<Option<Vec<_>>>()?;
+    let [first, .., last] = &words[..] else {
+        return None;
+    };
+    if !words.iter().all(|word| is_bare_word(word)) {
+        return None;
+    }
+    let prefix = words
+        .iter()
+        .fold(*first, |prefix, word| common_prefix(prefix, word));
+    let shortest = words.iter().map(|word| word.len()).min().unwrap_or(0);
+    let suffix_len = (0..=shortest - prefix.len())
+        .rev()
+        .find(|&len| {
+            words
+                .iter()
+                .all(|word| word.ends_with(&last[last.len() - len..]))
+        })
+        .unwrap_or(0);
+    let suffix = &last[last.len() - suffix_len..];
+    let alternatives: Vec<_> = words
+        .iter()
+        .map(|word| &word[prefix.len()..word.len() - suffix_len])
+        .collect();
+    // A comma would split the group.
+    if alternatives.iter().any(|word| word.contains(',')) {
+        return None;
+    }
+    Some(format!("{prefix}{{{}}}{suffix}", alternatives.join(",")))
+}
+
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let len = a
+        .char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map_or(a.len().min(b.len()), |((index, _), _)| index);
+    &a[..len]
+}
+
+/// Whether a command argument can be written without quotes and is read back as the same word.
+fn is_bare_word(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || b"_-./:=,+%^~*".contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate;
+    use ruff_python_ast::Stmt;
+    use ruff_python_codegen::{Generator, Indentation};
+    use ruff_source_file::LineEnding;
+
+    fn lowered(body: &[Stmt]) -> Vec<String> {
+        let indentation = Indentation::default();
+        body.iter()
+            .map(|stmt| Generator::new(&indentation, LineEnding::Lf).stmt(stmt))
+            .collect()
+    }
+
+    /// Generates the xonsh code for `src` and checks that it parses to the same tree.
+    fn round_trip(src: &str) -> String {
+        let parsed = ruff_python_parser::parse_module(src).unwrap();
+        let code = generate(parsed.suite());
+        let reparsed = ruff_python_parser::parse_module(&code).unwrap();
+        assert_eq!(lowered(parsed.suite()), lowered(reparsed.suite()), "{code}");
+        code
+    }
+
+    #[test]
+    fn test_generate_procs() {
+        assert_eq!(
+            round_trip("x = $(ls -l | grep $HOME > out.txt)\nls tmp &\n"),
+            "x = $(ls -l | grep $HOME > out.txt)\n![ls tmp &]\n"
+        );
+        assert_eq!(
+            round_trip("$[echo a@$(which python)b @(x) 'a b' (x + 1) o > /dev/null]\n"),
+            "$[echo a@$(which python)b @(x) 'a b' (x + 1) 'o' > /dev/null]\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_env_prefixes() {
+        assert_eq!(
+            round_trip("FOO=bar BAZ=$HOME make test\nx = $(A= B='a b' env)\n"),
+            "![FOO=bar BAZ=$HOME make test]\nx = $(A= B='a b' env)\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_cmd_chains() {
+        assert_eq!(
+            round_trip("make && make install || echo failed\nx = $(git pull and git log | head)\n"),
+            "![make && make install || echo failed]\nx = $(git pull && git log | head)\n"
+        );
+        assert_eq!(
+            round_trip("(![a] or ![b]) and ![c]\n$[a] and $[b]\n"),
+            "![a || b] and ![c]\n$[a] and $[b]\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_redirects() {
+        assert_eq!(
+            round_trip("$[make 2>&1 e>>err.log <>dev > @(log) |& tee out e| grep x]\n"),
+            "$[make 2>&1 e>> err.log <> dev > @(log) |& tee out e| grep x]\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_process_substitution() {
+        assert_eq!(
+            round_trip("cat x <(sort a|uniq) <(sort b)\nls x >(gzip > out.gz) 2> >(tee err)\n"),
+            "![cat x <(sort a | uniq) <(sort b)]\n![ls x >(gzip > out.gz) 2> >(tee err)]\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_brace_expansion() {
+        assert_eq!(
+            round_trip("cp file.{txt,bak} log{1..3}\n$[ls {$A,b}/x]\n"),
+            "![cp file.{txt,bak} log{1,2,3}]\n$[ls ([$A + '/x', 'b/x'])]\n"
+        );
+        assert_eq!(
+            round_trip("x = ['a', 'b']\necho @(['a', 'b'])\n"),
+            "x = ['a', 'b']\n![echo @(['a', 'b'])]\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_nested_proc_args() {
+        assert_eq!(
+            round_trip("$[cp --out=${d}/x ${'HO' + 'ME'} a!(which b) ![c] $[d]e]\n"),
+            "$[cp --out=${d}/x ${'HO' + 'ME'} a!(which b) ![c] $[d]e]\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_spec_modifiers() {
+        assert_eq!(
+            round_trip("x = $(@json curl a | @lines FOO=1 jq)\n"),
+            "x = $(@json curl a | @lines FOO=1 jq)\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_shell_words() {
+        assert_eq!(
+            round_trip("$[pip install --version=1.2.3 08 a#b]\n"),
+            "$[pip install --version=1.2.3 08 'a#b']\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_macros() {
+        assert_eq!(
+            round_trip(
+                "def f():\n    with! x as y:\n        some more\n\n        text\n    f!(a, b)\n"
+            ),
+            "def f():\n    with! x as y:\n        some more\n\n        text\n    f!(a, b)\n"
+        );
+        assert_eq!(
+            round_trip("print(p'/tmp', `a.*`, g`*.py`, range?)\n"),
+            "print(p'/tmp', `a.*`, g`*.py`, range?)\n"
+        );
+        assert_eq!(
+            round_trip("print(pf'{x}/y', p`a.*`, pg`*.py`, @foo`b.*`)\n"),
+            "print(pf'{x}/y', p`a.*`, pg`*.py`, @foo`b.*`)\n"
+        );
+    }
+}
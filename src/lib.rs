@@ -1,9 +1,15 @@
 mod annotate_src;
+mod cli;
+pub mod formatter;
+pub mod generator;
 mod lexer;
+mod lint;
 mod location;
 pub mod parser;
 mod parser_test;
+mod semantic;
 pub mod test_utils;
+mod transpile;
 
 use pyo3::prelude::*;
 
@@ -15,12 +21,32 @@ mod xonsh_rd_parser {
     #[pymodule_export]
     use parser::PyParser;
 
+    #[pymodule_export]
+    use transpile::SourceMap;
+
+    #[pymodule_export]
+    use lint::Diagnostic;
+
     #[pyfunction]
     #[pyo3(signature = (lines=None, file_name=None))]
     fn get_big_py_file(lines: Option<usize>, file_name: Option<&str>) -> PyResult<String> {
         Ok(test_utils::get_big_py_file(lines, file_name))
     }
 
+    /// Entry point of the `xonsh-rd-parser` console script.
+    #[pyfunction]
+    #[pyo3(signature = (argv=None))]
+    fn main(py: Python<'_>, argv: Option<Vec<String>>) -> PyResult<i32> {
+        let argv = match argv {
+            Some(argv) => argv,
+            None => {
+                let argv: Vec<String> = py.import("sys")?.getattr("argv")?.extract()?;
+                argv.into_iter().skip(1).collect()
+            }
+        };
+        cli::run(py, &argv)
+    }
+
     #[pyfunction]
     fn is_debug_build() -> bool {
         cfg!(debug_assertions)
@@ -0,0 +1,434 @@
+//! Lint rules for the xonsh-specific parts of a module.
+//!
+//! The rules run on the lowered tree and recognize the `__xonsh__` calls that subprocess
+//! captures, `@(...)` and glob strings are turned into, so they report the same issues whatever
+//! form the code was written in.
+use pyo3::prelude::*;
+use ruff_python_ast::visitor::{self, Visitor};
+use ruff_python_ast::{self as ast, Expr, Stmt};
+use ruff_source_file::SourceCode;
+use ruff_text_size::{Ranged, TextRange};
+
+use crate::generator::{env_slice, method_call, single_string, subproc_groups, xonsh_call};
+
+/// Redirect prefixes that write the standard output, like `>`, `o>` or `a>`.
+const STDOUT_REDIRECTS: &[&str] = &["", "o", "out", "1", "a", "all", "&"];
+
+/// A lint rule, identified by a stable code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Rule {
+    /// `if $(cmd):` tests the output of the command, not whether it succeeded.
+    CaptureInCondition,
+    /// `rm -rf $VAR/` removes from the root if the variable is empty.
+    UnquotedEnvInRm,
+    /// `@('x')` can be written as the plain argument.
+    ConstantInterpolation,
+    /// `> a > b` only writes to one of the files.
+    DuplicateStdoutRedirect,
+    /// `$(cmd &)` captures nothing, as the command runs in the background.
+    BackgroundInCapture,
+    /// `x = $[cmd]` is always `None`, as `$[...]` does not capture the command, so its value is
+    /// only meaningful when it is discarded.
+    UncapturedValueUsed,
+    /// ``g`file.txt` `` matches at most the file itself.
+    GlobWithoutWildcard,
+}
+
+impl Rule {
+    pub(crate) const ALL: &[Rule] = &[
+        Rule::CaptureInCondition,
+        Rule::UnquotedEnvInRm,
+        Rule::ConstantInterpolation,
+        Rule::DuplicateStdoutRedirect,
+        Rule::BackgroundInCapture,
+        Rule::UncapturedValueUsed,
+        Rule::GlobWithoutWildcard,
+    ];
+
+    pub(crate) const fn code(self) -> &'static str {
+        match self {
+            Rule::CaptureInCondition => "XSH001",
+            Rule::UnquotedEnvInRm => "XSH002",
+            Rule::ConstantInterpolation => "XSH003",
+            Rule::DuplicateStdoutRedirect => "XSH004",
+            Rule::BackgroundInCapture => "XSH005",
+            Rule::UncapturedValueUsed => "XSH006",
+            Rule::GlobWithoutWildcard => "XSH007",
+        }
+    }
+
+    pub(crate) const fn name(self) -> &'static str {
+        match self {
+            Rule::CaptureInCondition => "capture-in-condition",
+            Rule::UnquotedEnvInRm => "unquoted-env-in-rm",
+            Rule::ConstantInterpolation => "constant-interpolation",
+            Rule::DuplicateStdoutRedirect => "duplicate-stdout-redirect",
+            Rule::BackgroundInCapture => "background-in-capture",
+            Rule::UncapturedValueUsed => "uncaptured-value-used",
+            Rule::GlobWithoutWildcard => "glob-without-wildcard",
+        }
+    }
+
+    const fn message(self) -> &'static str {
+        match self {
+            Rule::CaptureInCondition => {
+                "`$(...)` tests the output of the command, use `!(...)` to test whether it succeeded"
+            }
+            Rule::UnquotedEnvInRm => {
+                "environment variable in an `rm` argument expands to nothing when it is unset"
+            }
+            Rule::ConstantInterpolation => "`@(...)` wraps a constant, write the argument directly",
+            Rule::DuplicateStdoutRedirect => "the standard output is redirected more than once",
+            Rule::BackgroundInCapture => "a background command inside `$(...)` captures nothing",
+            Rule::UncapturedValueUsed => {
+                "the value of `$[...]` is always `None`, use `!(...)` or `$(...)` to capture the command"
+            }
+            Rule::GlobWithoutWildcard => "glob pattern has no wildcard",
+        }
+    }
+}
+
+/// Returns the rules matching the given codes, code prefixes (`XSH00`) or names.
+pub(crate) fn select_rules<S: AsRef<str>>(selectors: &[S]) -> Result<Vec<Rule>, String> {
+    let mut rules = Vec::new();
+    for selector in selectors {
+        let selector = selector.as_ref();
+        let matched = Rule::ALL
+            .iter()
+            .filter(|rule| rule.code().starts_with(selector) || rule.name() == selector)
+            .copied()
+            .collect::<Vec<_>>();
+        if selector.is_empty() || matched.is_empty() {
+            return Err(format!("unknown lint rule `{selector}`"));
+        }
+        for rule in matched {
+            if !rules.contains(&rule) {
+                rules.push(rule);
+            }
+        }
+    }
+    Ok(rules)
+}
+
+/// A lint violation.
+#[pyclass(module = "xonsh_rd_parser", frozen)]
+#[derive(Debug, Clone)]
+pub(crate) struct Diagnostic {
+    rule: Rule,
+    range: TextRange,
+    /// `(lineno, col_offset, end_lineno, end_col_offset)` like Python's `ast`.
+    span: (usize, usize, usize, usize),
+}
+
+impl Diagnostic {
+    pub(crate) fn code(&self) -> &'static str {
+        self.rule.code()
+    }
+
+    pub(crate) fn message(&self) -> &'static str {
+        self.rule.message()
+    }
+
+    pub(crate) fn location(&self) -> (usize, usize) {
+        (self.span.0, self.span.1)
+    }
+}
+
+#[pymethods]
+impl Diagnostic {
+    #[getter]
+    #[pyo3(name = "code")]
+    fn py_code(&self) -> &'static str {
+        self.code()
+    }
+
+    #[getter]
+    fn name(&self) -> &'static str {
+        self.rule.name()
+    }
+
+    #[getter]
+    #[pyo3(name = "message")]
+    fn py_message(&self) -> &'static str {
+        self.message()
+    }
+
+    /// The `(start, end)` offsets in the source.
+    #[getter]
+    fn range(&self) -> (usize, usize) {
+        (self.range.start().to_usize(), self.range.end().to_usize())
+    }
+
+    #[getter]
+    fn lineno(&self) -> usize {
+        self.span.0
+    }
+
+    #[getter]
+    fn col_offset(&self) -> usize {
+        self.span.1
+    }
+
+    #[getter]
+    fn end_lineno(&self) -> usize {
+        self.span.2
+    }
+
+    #[getter]
+    fn end_col_offset(&self) -> usize {
+        self.span.3
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<Diagnostic {} at {}:{}: {}>",
+            self.code(),
+            self.span.0,
+            self.span.1,
+            self.message()
+        )
+    }
+}
+
+/// Runs the given rules over the module `body` and returns the violations in source order.
+pub(crate) fn lint(source: &SourceCode, body: &[Stmt], rules: &[Rule]) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        rules,
+        found: Vec::new(),
+        statement: None,
+    };
+    linter.visit_body(body);
+
+    let mut found = linter.found;
+    found.sort_by_key(|(rule, range)| (range.start(), rule.code()));
+    // `not $(...)` is seen both as a condition and as an operand of `not`.
+    found.dedup();
+    found
+        .into_iter()
+        .map(|(rule, range)| {
+            let start = source.line_column(range.start());
+            let end = source.line_column(range.end());
+            Diagnostic {
+                rule,
+                range,
+                span: (
+                    start.line.get(),
+                    start.column.to_zero_indexed(),
+                    end.line.get(),
+                    end.column.to_zero_indexed(),
+                ),
+            }
+        })
+        .collect()
+}
+
+struct Linter<'r> {
+    rules: &'r [Rule],
+    found: Vec<(Rule, TextRange)>,
+    /// The range of the value of the expression statement being visited, which is discarded.
+    statement: Option<TextRange>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, rule: Rule, range: TextRange) {
+        if self.rules.contains(&rule) {
+            self.found.push((rule, range));
+        }
+    }
+
+    /// Checks an expression whose truthiness is tested.
+    fn condition(&mut self, expr: &Expr) {
+        match expr {
+            Expr::BoolOp(bool_op) => {
+                for value in &bool_op.values {
+                    self.condition(value);
+                }
+            }
+            Expr::UnaryOp(unary) if unary.op == ast::UnaryOp::Not => self.condition(&unary.operand),
+            _ => {
+                if let Some(("out", _)) = subproc_groups(expr) {
+                    self.report(Rule::CaptureInCondition, expr.range());
+                }
+            }
+        }
+    }
+
+    fn subproc(&mut self, expr: &Expr, method: &str, groups: &[&ast::Arguments]) {
+        if method == "run" && self.statement != Some(expr.range()) {
+            self.report(Rule::UncapturedValueUsed, expr.range());
+        }
+        for group in groups {
+            self.cmd_group(method, group);
+        }
+    }
+
+    fn cmd_group(&mut self, method: &str, group: &ast::Arguments) {
+        let is_rm = group.args.first().and_then(single_string) == Some("rm");
+        for arg in &group.args {
+            let parts = arg_parts(arg);
+            if is_rm && parts.iter().any(|part| env_slice(part).is_some()) {
+                self.report(Rule::UnquotedEnvInRm, arg.range());
+            }
+            for part in parts {
+                if let Some([value]) = xonsh_call(part, "list_of_strs_or_callables")
+                    && value.is_literal_expr()
+                {
+                    self.report(Rule::ConstantInterpolation, part.range());
+                }
+            }
+        }
+
+        for keyword in &group.keywords {
+            match (
+                keyword.arg.as_ref().map(ast::Identifier::as_str),
+                &keyword.value,
+            ) {
+                (Some("redirects"), Expr::Dict(dict)) => {
+                    let stdout = dict.items.iter().filter(|item| {
+                        item.key
+                            .as_ref()
+                            .and_then(single_string)
+                            .and_then(|key| key.strip_suffix('>'))
+                            .is_some_and(|key| {
+                                STDOUT_REDIRECTS.contains(&key.strip_suffix('>').unwrap_or(key))
+                            })
+                    });
+                    for item in stdout.skip(1) {
+                        self.report(Rule::DuplicateStdoutRedirect, item.range());
+                    }
+                }
+                (Some("bg"), _) if matches!(method, "out" | "inject") => {
+                    self.report(Rule::BackgroundInCapture, keyword.range());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Returns the last value of a chain like `$[a && b]`, which is the one that a statement
+/// discards when the chain doesn't short-circuit.
+fn last_chained(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::BoolOp(bool_op) => bool_op.values.last().map_or(expr, last_chained),
+        _ => expr,
+    }
+}
+
+impl<'a> Visitor<'a> for Linter<'_> {
+    fn visit_stmt(&mut self, stmt: &'a Stmt) {
+        let outer = self.statement.take();
+        match stmt {
+            Stmt::Expr(expr) => self.statement = Some(last_chained(&expr.value).range()),
+            Stmt::If(if_stmt) => {
+                self.condition(&if_stmt.test);
+                for clause in &if_stmt.elif_else_clauses {
+                    if let Some(test) = &clause.test {
+                        self.condition(test);
+                    }
+                }
+            }
+            Stmt::While(while_stmt) => self.condition(&while_stmt.test),
+            Stmt::Assert(assert) => self.condition(&assert.test),
+            _ => {}
+        }
+        visitor::walk_stmt(self, stmt);
+        self.statement = outer;
+    }
+
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        match expr {
+            Expr::If(if_expr) => self.condition(&if_expr.test),
+            Expr::UnaryOp(unary) if unary.op == ast::UnaryOp::Not => self.condition(&unary.operand),
+            _ => {}
+        }
+        if let Some((method, groups)) = subproc_groups(expr) {
+            self.subproc(expr, method, &groups);
+        }
+        if let Some((receiver, "glob", arguments)) = method_call(expr)
+            && arguments.args.is_empty()
+            && let Some([pattern]) = xonsh_call(receiver, "Pattern")
+            && single_string(pattern).is_some_and(|value| !value.contains(['*', '?', '[']))
+        {
+            self.report(Rule::GlobWithoutWildcard, expr.range());
+        }
+        visitor::walk_expr(self, expr);
+    }
+
+    fn visit_comprehension(&mut self, comprehension: &'a ast::Comprehension) {
+        for condition in &comprehension.ifs {
+            self.condition(condition);
+        }
+        visitor::walk_comprehension(self, comprehension);
+    }
+}
+
+/// Splits an argument of a command into its adjacent parts, like `$HOME` and `/` in `$HOME/`.
+fn arg_parts(arg: &Expr) -> Vec<&Expr> {
+    let mut parts = Vec::new();
+    let mut expr = arg;
+    while let Expr::BinOp(bin_op) = expr
+        && bin_op.op == ast::Operator::Add
+    {
+        parts.push(bin_op.right.as_ref());
+        expr = &bin_op.left;
+    }
+    parts.push(expr);
+    parts.reverse();
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use ruff_source_file::{LineIndex, SourceCode};
+
+    use super::{Rule, lint, select_rules};
+
+    fn codes(src: &str) -> Vec<(&'static str, usize)> {
+        let parsed = ruff_python_parser::parse_module(src).unwrap();
+        let index = LineIndex::from_source_text(src);
+        let source = SourceCode::new(src, &index);
+        lint(&source, parsed.suite(), Rule::ALL)
+            .iter()
+            .map(|diagnostic| (diagnostic.code(), diagnostic.location().0))
+            .collect()
+    }
+
+    #[test]
+    fn test_rules() {
+        let src = "\
+if $(which git):
+    pass
+$[rm -rf $PREFIX/]
+$[echo @('a') @(x)]
+$[echo hi > out.txt 2> err.txt o> other.txt]
+x = $(make build &)
+y = $[ls]
+$[ls]
+z = g`file.txt` + g`*.txt`
+$[make && make install]
+";
+        assert_eq!(
+            codes(src),
+            [
+                ("XSH001", 1),
+                ("XSH002", 3),
+                ("XSH003", 4),
+                ("XSH004", 5),
+                ("XSH005", 6),
+                ("XSH006", 7),
+                ("XSH007", 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_rules() {
+        assert_eq!(
+            select_rules(&["XSH001", "glob-without-wildcard"]),
+            Ok(vec![Rule::CaptureInCondition, Rule::GlobWithoutWildcard])
+        );
+        assert_eq!(select_rules(&["XSH"]), Ok(Rule::ALL.to_vec()));
+        assert!(select_rules(&["XSH100"]).is_err());
+    }
+}
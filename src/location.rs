@@ -38,8 +38,8 @@ pub(crate) trait HasKind {
             Indent => "INDENT",
             Dedent => "DEDENT",
             NonLogicalNewline => "NL",
-            IpyEscapeCommand => unreachable!(),
-            Unknown => "ErrorToken",
+            Word => "WORD",
+            IpyEscapeCommand | Unknown => "ErrorToken",
             _ => {
                 if self.kind().is_operator() {
                     "OP"
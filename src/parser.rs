@@ -1,20 +1,47 @@
 use crate::annotate_src::to_syntax_err;
+use crate::formatter::{FormatError, format};
+use crate::generator::generate;
 use crate::lexer::{LexerExt, Token};
+use crate::lint::{Diagnostic, Rule, lint, select_rules};
+use crate::semantic;
+use crate::transpile::{SourceMap, transpile};
 use py_ast::ast_module::AstModule;
 use py_ast::to_ast::ToAst;
+use pyo3::exceptions::{PyRuntimeError, PySyntaxWarning, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::PyString;
-use ruff_python_ast::ModModule;
-use ruff_python_parser::{ParseError, Parsed};
+use ruff_python_ast::{ModModule, PythonVersion};
+use ruff_python_parser::{Mode, ParseError, ParseErrorType, ParseOptions, Parsed, PatternSeverity};
 use ruff_source_file::{LineIndex, SourceCode};
+use ruff_text_size::TextRange;
+use std::panic::{UnwindSafe, catch_unwind};
 
 // type ParseResult = PyResult<Parsed<ModModule>>;
 
+/// Runs the parser, turning a panic into a syntax error as the last line of defense, so that a
+/// bug in the parser can't take down the shell.
+fn catch_panic<T>(parse: impl FnOnce() -> T + UnwindSafe) -> Result<T, ParseError> {
+    catch_unwind(parse).map_err(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown error");
+        ParseError {
+            error: ParseErrorType::InternalError(msg.to_string()),
+            location: TextRange::default(),
+        }
+    })
+}
+
 #[pyclass(name = "Parser", module = "xonsh_rd_parser")]
 pub struct PyParser {
     src: Py<PyString>,
     file: String,
     line_index: LineIndex,
+    /// The version of the running interpreter, used for the semantic syntax checks.
+    python_version: PythonVersion,
+    options: ParseOptions,
 }
 
 impl PyParser {
@@ -26,52 +53,201 @@ impl PyParser {
     fn convert_err(&self, code: &SourceCode, error: &ParseError) -> PyErr {
         to_syntax_err(self.file.as_str(), code, error)
     }
-    fn parse_module(&self, src: &SourceCode) -> PyResult<Parsed<ModModule>> {
-        ruff_python_parser::parse_module(src.text()).map_err(|err| self.convert_err(src, &err))
+    /// Emits a non-fatal error, like an invalid regex literal, as a `SyntaxWarning`.
+    fn warn(&self, src: &SourceCode, warning: &ParseError) -> PyResult<()> {
+        let lineno = src.line_column(warning.location.start()).line.get();
+        Python::attach(|py| {
+            py.import("warnings")?.call_method1(
+                "warn_explicit",
+                (
+                    warning.error.to_string(),
+                    py.get_type::<PySyntaxWarning>(),
+                    self.file.as_str(),
+                    lineno,
+                ),
+            )?;
+            Ok(())
+        })
+    }
+    /// Lexes the source as Python tokens, without the shell words of commands, like the xonsh
+    /// lexer that `subproc_toks` and `split` work on, raising the last lexical error unless
+    /// `tolerant`.
+    fn python_tokens(&self, py: Python<'_>, tolerant: bool) -> PyResult<Vec<Token>> {
+        let code = self.code(py)?;
+        let text = code.text();
+        let (tokens, err) = catch_panic(|| ruff_python_parser::lex_module(text))
+            .unwrap_or_else(|err| (Vec::new(), Some(err)));
+        if let Some(err) = err
+            && !tolerant
+        {
+            return Err(self.convert_err(&code, &err));
+        }
+
+        let tokens = tokens
+            .iter()
+            .map(|t| Token::new(t, &code, Some(self.src.clone_ref(py))))
+            .collect::<Vec<_>>();
+        Ok(tokens)
+    }
+    /// Parses the module and runs the semantic syntax checks on it. In `tolerant` mode, all of
+    /// their errors are emitted as a `SyntaxWarning` instead of raising the first one.
+    fn parse_module(&self, src: &SourceCode, tolerant: bool) -> PyResult<Parsed<ModModule>> {
+        let text = src.text();
+        let options = self.options.clone();
+        let parsed = catch_panic(|| ruff_python_parser::parse(text, options))
+            .flatten()
+            .map(|parsed| {
+                parsed
+                    .try_into_module()
+                    .expect("a module is parsed in module mode")
+            })
+            .map_err(|err| self.convert_err(src, &err))?;
+        for warning in parsed.warnings() {
+            self.warn(src, warning)?;
+        }
+        for error in semantic::check(src.text(), parsed.suite(), self.python_version) {
+            let error = ParseError {
+                error: ParseErrorType::SemanticError(error.kind),
+                location: error.range,
+            };
+            if !tolerant {
+                return Err(self.convert_err(src, &error));
+            }
+            self.warn(src, &error)?;
+        }
+        Ok(parsed)
     }
 }
 
 #[pymethods]
 impl PyParser {
+    /// `ctx` holds the names defined in Python, like the context of xonsh's `Execer`. When it is
+    /// given, a line that starts with another name, like `ls -l`, is parsed as a command.
+    ///
+    /// Invalid regex and glob literals, like `` `(a` ``, are reported as a `SyntaxWarning`, or
+    /// as a `SyntaxError` with `strict_patterns`.
     #[new]
-    #[pyo3(signature = (src, file_name = None))]
-    fn new(src: Bound<'_, PyString>, file_name: Option<&'_ str>) -> PyResult<Self> {
+    #[pyo3(signature = (src, file_name = None, ctx = None, strict_patterns = false))]
+    pub(crate) fn new(
+        src: Bound<'_, PyString>,
+        file_name: Option<&'_ str>,
+        ctx: Option<Bound<'_, PyAny>>,
+        strict_patterns: bool,
+    ) -> PyResult<Self> {
         let file = file_name.unwrap_or("<code>").to_string();
+        let mut options = ParseOptions::from(Mode::Module);
+        if strict_patterns {
+            options = options.with_pattern_severity(PatternSeverity::Error);
+        }
+        if let Some(ctx) = ctx {
+            let builtins = src.py().import("builtins")?.dir()?;
+            let names = ctx
+                .try_iter()?
+                .chain(builtins.try_iter()?)
+                .map(|name| -> PyResult<String> { Ok(name?.extract()?) })
+                .collect::<PyResult<Vec<_>>>()?;
+            options = options.with_known_names(names);
+        }
         let line_index = LineIndex::from_source_text(src.to_str()?);
+        let version = src.py().version_info();
         Ok(Self {
+            python_version: PythonVersion {
+                major: version.major,
+                minor: version.minor,
+            },
             src: src.into(),
             file,
             line_index,
+            options,
         })
     }
 
-    fn parse(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+    /// Parses the source into a Python `ast.Module`. With `tolerant`, the errors that don't stop
+    /// the parser, like `return` outside of a function, are all emitted as a `SyntaxWarning`
+    /// instead of raising the first one.
+    #[pyo3(signature = (tolerant = false))]
+    fn parse(&self, py: Python<'_>, tolerant: bool) -> PyResult<Py<PyAny>> {
         let source_code = self.code(py)?;
-        let parsed = self.parse_module(&source_code)?;
+        let parsed = self.parse_module(&source_code, tolerant)?;
         let tree = parsed.into_syntax();
         let module = AstModule::new(py, &source_code)?;
         tree.to_ast(&module)
     }
 
+    /// Transpiles the source to Python code and returns it along with its source map.
+    pub(crate) fn transpile(&self, py: Python<'_>) -> PyResult<(String, SourceMap)> {
+        let source_code = self.code(py)?;
+        let tree = self.parse_module(&source_code, false)?.into_syntax();
+        Ok(transpile(&source_code, &tree.body, &self.file))
+    }
+
+    /// Parses the source and generates xonsh code back from the tree.
+    fn unparse(&self, py: Python<'_>) -> PyResult<String> {
+        let source_code = self.code(py)?;
+        let tree = self.parse_module(&source_code, false)?.into_syntax();
+        Ok(generate(&tree.body))
+    }
+
+    /// Formats the source.
+    pub(crate) fn format(&self, py: Python<'_>) -> PyResult<String> {
+        let source_code = self.code(py)?;
+        let text = source_code.text();
+        let formatted =
+            catch_panic(|| format(text)).map_err(|err| self.convert_err(&source_code, &err))?;
+        formatted.map_err(|err| match err {
+            FormatError::Parse(err) => self.convert_err(&source_code, &err),
+            FormatError::Unstable => PyRuntimeError::new_err(err.to_string()),
+        })
+    }
+
+    /// Runs the xonsh lint rules, all of them unless some codes, code prefixes or names are
+    /// selected.
+    #[pyo3(signature = (select = None))]
+    pub(crate) fn lint(
+        &self,
+        py: Python<'_>,
+        select: Option<Vec<String>>,
+    ) -> PyResult<Vec<Diagnostic>> {
+        let rules = match select {
+            Some(select) => select_rules(&select).map_err(PyValueError::new_err)?,
+            None => Rule::ALL.to_vec(),
+        };
+        let source_code = self.code(py)?;
+        let tree = self.parse_module(&source_code, false)?.into_syntax();
+        Ok(lint(&source_code, &tree.body, &rules))
+    }
+
     #[staticmethod]
     pub fn parse_file(py: Python<'_>, path: &str) -> PyResult<Py<PyAny>> {
-        let src = std::fs::read_to_string(path).unwrap();
+        let src = std::fs::read_to_string(path)?;
         let src = PyString::new(py, &src);
-        PyParser::new(src, Some(path))?.parse(py)
+        PyParser::new(src, Some(path), None, false)?.parse(py, false)
     }
 
+    /// Returns the tokens of the source as the parser sees them, with the arguments of commands
+    /// as `Word` tokens, raising the first lexical error unless `tolerant`.
     #[pyo3(signature = (tolerant=false))]
     fn tokens(&self, py: Python<'_>, tolerant: Option<bool>) -> PyResult<Vec<Token>> {
         let tolerant = tolerant.unwrap_or(false);
         let code = self.code(py)?;
-        let (tokens, err) = ruff_python_parser::lex_module(code.text());
-        if let Some(err) = err
-            && !tolerant
+        let text = code.text();
+        let options = self.options.clone();
+        let parsed = match catch_panic(|| ruff_python_parser::parse_unchecked(text, options)) {
+            Ok(parsed) => parsed,
+            Err(_) if tolerant => return Ok(Vec::new()),
+            Err(err) => return Err(self.convert_err(&code, &err)),
+        };
+        if !tolerant
+            && let Some(err) = parsed
+                .errors()
+                .iter()
+                .find(|err| matches!(err.error, ParseErrorType::Lexical(_)))
         {
-            return Err(self.convert_err(&code, &err));
+            return Err(self.convert_err(&code, err));
         }
 
-        let tokens = tokens
+        let tokens = parsed
+            .tokens()
             .iter()
             .map(|t| Token::new(t, &code, Some(self.src.clone_ref(py))))
             .collect::<Vec<_>>();
@@ -92,7 +268,7 @@ impl PyParser {
         let mincol = mincol.unwrap_or(-1);
         let returnline = returnline.unwrap_or(false);
         let greedy = greedy.unwrap_or(false);
-        let tokens = self.tokens(py, None).ok().unwrap_or_default();
+        let tokens = self.python_tokens(py, false).ok().unwrap_or_default();
         let result = if let Some(range) = tokens.find_subproc_line(mincol, maxcol, greedy) {
             let line = format!("![{}]", &src[range]);
 
@@ -115,7 +291,7 @@ impl PyParser {
     /// Splits a string into a list of strings which are whitespace-separated tokens in proc mode.
     fn split(&self, py: Python<'_>) -> PyResult<Vec<String>> {
         let src = self.src.to_str(py)?;
-        let result = self.tokens(py, Some(true))?.split_ws(src);
+        let result = self.python_tokens(py, true)?.split_ws(src);
         Ok(result)
     }
 }
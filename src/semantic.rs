@@ -0,0 +1,459 @@
+//! Runs the [`SemanticSyntaxChecker`] over a parsed xonsh module.
+//!
+//! The parser only reports the errors it can see from the tokens. The checker finds the ones
+//! that depend on the enclosing scope, like `return` outside of a function or `await` outside of
+//! an async one. It runs on the lowered tree, so the Python code that `with!` macros and
+//! subprocess expressions are turned into is checked too.
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use ruff_python_ast::visitor::{self, Visitor};
+use ruff_python_ast::{self as ast, Expr, PythonVersion, Stmt};
+use ruff_python_parser::semantic_errors::{
+    LazyImportContext, SemanticSyntaxChecker, SemanticSyntaxContext, SemanticSyntaxError,
+};
+use ruff_text_size::TextRange;
+
+/// Returns the semantic syntax errors of the module `body` in source order.
+pub(crate) fn check(
+    source: &str,
+    body: &[Stmt],
+    python_version: PythonVersion,
+) -> Vec<SemanticSyntaxError> {
+    let mut context = CheckerContext {
+        checker: SemanticSyntaxChecker::new(),
+        errors: RefCell::default(),
+        python_version,
+        source,
+        scopes: vec![Scope::new(ScopeKind::Module, body, None)],
+        in_try: false,
+    };
+    context.visit_body(body);
+    let mut errors = context.errors.into_inner();
+    errors.sort_by_key(|error| error.range.start());
+    errors
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScopeKind {
+    Module,
+    Function { is_async: bool },
+    Lambda,
+    Class,
+    Comprehension { is_async: bool, is_generator: bool },
+}
+
+struct Scope {
+    kind: ScopeKind,
+    /// The number of `for` and `while` loops around the current statement in this scope.
+    loops: usize,
+    names: ScopeNames,
+    parameters: HashSet<String>,
+}
+
+impl Scope {
+    fn new(kind: ScopeKind, body: &[Stmt], parameters: Option<&ast::Parameters>) -> Self {
+        let mut names = ScopeNames::default();
+        names.visit_body(body);
+        Self {
+            kind,
+            loops: 0,
+            names,
+            parameters: parameters
+                .into_iter()
+                .flat_map(ast::Parameters::iter)
+                .map(|parameter| parameter.name().to_string())
+                .collect(),
+        }
+    }
+
+    fn binds(&self, name: &str) -> bool {
+        !self.names.globals.contains_key(name)
+            && (self.names.bindings.contains(name) || self.parameters.contains(name))
+    }
+}
+
+/// The `global` declarations and bound names of a scope, not counting nested scopes.
+#[derive(Default)]
+struct ScopeNames {
+    globals: HashMap<String, TextRange>,
+    bindings: HashSet<String>,
+}
+
+impl<'a> Visitor<'a> for ScopeNames {
+    fn visit_stmt(&mut self, stmt: &'a Stmt) {
+        match stmt {
+            Stmt::FunctionDef(def) => {
+                self.bindings.insert(def.name.to_string());
+            }
+            Stmt::ClassDef(class) => {
+                self.bindings.insert(class.name.to_string());
+            }
+            Stmt::Global(global) => {
+                for name in &global.names {
+                    self.globals.entry(name.to_string()).or_insert(global.range);
+                }
+            }
+            Stmt::Nonlocal(nonlocal) => {
+                self.bindings
+                    .extend(nonlocal.names.iter().map(ToString::to_string));
+            }
+            Stmt::Import(ast::StmtImport { names, .. })
+            | Stmt::ImportFrom(ast::StmtImportFrom { names, .. }) => {
+                for alias in names {
+                    let name = alias.asname.as_ref().unwrap_or(&alias.name).as_str();
+                    let name = name.split('.').next().unwrap_or(name);
+                    self.bindings.insert(name.to_string());
+                }
+            }
+            _ => visitor::walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        match expr {
+            Expr::Name(name) if !name.ctx.is_load() => {
+                self.bindings.insert(name.id.to_string());
+            }
+            Expr::Lambda(_)
+            | Expr::ListComp(_)
+            | Expr::SetComp(_)
+            | Expr::DictComp(_)
+            | Expr::Generator(_) => {}
+            _ => visitor::walk_expr(self, expr),
+        }
+    }
+
+    fn visit_except_handler(&mut self, handler: &'a ast::ExceptHandler) {
+        let ast::ExceptHandler::ExceptHandler(handler_inner) = handler;
+        if let Some(name) = &handler_inner.name {
+            self.bindings.insert(name.to_string());
+        }
+        visitor::walk_except_handler(self, handler);
+    }
+
+    fn visit_pattern(&mut self, pattern: &'a ast::Pattern) {
+        let name = match pattern {
+            ast::Pattern::MatchAs(ast::PatternMatchAs { name, .. })
+            | ast::Pattern::MatchStar(ast::PatternMatchStar { name, .. }) => name.as_ref(),
+            ast::Pattern::MatchMapping(ast::PatternMatchMapping { rest, .. }) => rest.as_ref(),
+            _ => None,
+        };
+        if let Some(name) = name {
+            self.bindings.insert(name.to_string());
+        }
+        visitor::walk_pattern(self, pattern);
+    }
+}
+
+struct CheckerContext<'a> {
+    checker: SemanticSyntaxChecker,
+    errors: RefCell<Vec<SemanticSyntaxError>>,
+    python_version: PythonVersion,
+    source: &'a str,
+    scopes: Vec<Scope>,
+    in_try: bool,
+}
+
+impl CheckerContext<'_> {
+    fn with_semantic_checker(&mut self, f: impl FnOnce(&mut SemanticSyntaxChecker, &Self)) {
+        let mut checker = std::mem::take(&mut self.checker);
+        f(&mut checker, self);
+        self.checker = checker;
+    }
+
+    fn scope(&self) -> &Scope {
+        self.scopes
+            .last()
+            .expect("the module scope is never popped")
+    }
+
+    fn scope_mut(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("the module scope is never popped")
+    }
+
+    fn in_scope(&mut self, scope: Scope, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(scope);
+        f(self);
+        self.scopes.pop();
+    }
+
+    fn in_loop(&mut self, body: &[Stmt]) {
+        self.scope_mut().loops += 1;
+        self.visit_body(body);
+        self.scope_mut().loops -= 1;
+    }
+
+    fn visit_comprehension_scope<'b>(
+        &mut self,
+        kind: ScopeKind,
+        generators: &'b [ast::Comprehension],
+        elts: impl IntoIterator<Item = &'b Expr>,
+    ) {
+        for comprehension in generators {
+            self.visit_comprehension(comprehension);
+        }
+        self.in_scope(Scope::new(kind, &[], None), |context| {
+            for elt in elts {
+                context.visit_expr(elt);
+            }
+        });
+    }
+}
+
+impl SemanticSyntaxContext for CheckerContext<'_> {
+    fn future_annotations_or_stub(&self) -> bool {
+        false
+    }
+
+    fn lazy_import_context(&self) -> Option<LazyImportContext> {
+        match self.scope().kind {
+            ScopeKind::Function { .. } | ScopeKind::Lambda => {
+                return Some(LazyImportContext::Function);
+            }
+            ScopeKind::Class => return Some(LazyImportContext::Class),
+            ScopeKind::Module | ScopeKind::Comprehension { .. } => {}
+        }
+        self.in_try.then_some(LazyImportContext::TryExceptBlocks)
+    }
+
+    fn python_version(&self) -> PythonVersion {
+        self.python_version
+    }
+
+    fn source(&self) -> &str {
+        self.source
+    }
+
+    fn global(&self, name: &str) -> Option<TextRange> {
+        self.scope().names.globals.get(name).copied()
+    }
+
+    fn has_nonlocal_binding(&self, name: &str) -> bool {
+        self.scopes[..self.scopes.len() - 1]
+            .iter()
+            .rev()
+            .filter(|scope| matches!(scope.kind, ScopeKind::Function { .. } | ScopeKind::Lambda))
+            .any(|scope| scope.binds(name))
+    }
+
+    fn in_async_context(&self) -> bool {
+        for scope in self.scopes.iter().rev() {
+            match scope.kind {
+                ScopeKind::Function { is_async } => return is_async,
+                ScopeKind::Class | ScopeKind::Lambda => return false,
+                ScopeKind::Module | ScopeKind::Comprehension { .. } => {}
+            }
+        }
+        false
+    }
+
+    fn in_await_allowed_context(&self) -> bool {
+        for scope in self.scopes.iter().rev() {
+            match scope.kind {
+                ScopeKind::Function { .. } | ScopeKind::Lambda => return true,
+                ScopeKind::Class | ScopeKind::Module => return false,
+                ScopeKind::Comprehension { .. } => {}
+            }
+        }
+        false
+    }
+
+    fn in_yield_allowed_context(&self) -> bool {
+        matches!(
+            self.scope().kind,
+            ScopeKind::Function { .. } | ScopeKind::Lambda
+        )
+    }
+
+    fn in_sync_comprehension(&self) -> bool {
+        self.scopes.iter().any(|scope| {
+            matches!(
+                scope.kind,
+                ScopeKind::Comprehension {
+                    is_async: false,
+                    ..
+                }
+            )
+        })
+    }
+
+    fn in_class_body_comprehension(&self) -> bool {
+        for scope in self.scopes.iter().rev() {
+            match scope.kind {
+                ScopeKind::Comprehension { .. } => {}
+                ScopeKind::Class => return true,
+                ScopeKind::Module | ScopeKind::Function { .. } | ScopeKind::Lambda => return false,
+            }
+        }
+        false
+    }
+
+    fn in_module_scope(&self) -> bool {
+        self.scope().kind == ScopeKind::Module
+    }
+
+    fn in_function_scope(&self) -> bool {
+        matches!(self.scope().kind, ScopeKind::Function { .. })
+    }
+
+    fn in_generator_context(&self) -> bool {
+        matches!(
+            self.scope().kind,
+            ScopeKind::Comprehension {
+                is_generator: true,
+                ..
+            }
+        )
+    }
+
+    fn in_notebook(&self) -> bool {
+        false
+    }
+
+    fn report_semantic_error(&self, error: SemanticSyntaxError) {
+        self.errors.borrow_mut().push(error);
+    }
+
+    fn in_loop_context(&self) -> bool {
+        self.scope().loops > 0
+    }
+
+    fn is_bound_parameter(&self, name: &str) -> bool {
+        self.scope().parameters.contains(name)
+    }
+}
+
+impl<'a> Visitor<'a> for CheckerContext<'_> {
+    fn visit_stmt(&mut self, stmt: &'a Stmt) {
+        self.with_semantic_checker(|checker, context| checker.visit_stmt(stmt, context));
+        match stmt {
+            Stmt::ClassDef(class) => {
+                for decorator in &class.decorator_list {
+                    self.visit_decorator(decorator);
+                }
+                if let Some(type_params) = &class.type_params {
+                    self.visit_type_params(type_params);
+                }
+                if let Some(arguments) = &class.arguments {
+                    self.visit_arguments(arguments);
+                }
+                let scope = Scope::new(ScopeKind::Class, &class.body, None);
+                self.in_scope(scope, |context| context.visit_body(&class.body));
+            }
+            Stmt::FunctionDef(def) => {
+                for decorator in &def.decorator_list {
+                    self.visit_decorator(decorator);
+                }
+                if let Some(type_params) = &def.type_params {
+                    self.visit_type_params(type_params);
+                }
+                self.visit_parameters(&def.parameters);
+                if let Some(returns) = &def.returns {
+                    self.visit_annotation(returns);
+                }
+                let kind = ScopeKind::Function {
+                    is_async: def.is_async,
+                };
+                let scope = Scope::new(kind, &def.body, Some(&*def.parameters));
+                self.in_scope(scope, |context| context.visit_body(&def.body));
+            }
+            Stmt::For(for_) => {
+                self.visit_expr(&for_.target);
+                self.visit_expr(&for_.iter);
+                self.in_loop(&for_.body);
+                self.visit_body(&for_.orelse);
+            }
+            Stmt::While(while_) => {
+                self.visit_expr(&while_.test);
+                self.in_loop(&while_.body);
+                self.visit_body(&while_.orelse);
+            }
+            Stmt::Try(_) => {
+                let was_in_try = std::mem::replace(&mut self.in_try, true);
+                visitor::walk_stmt(self, stmt);
+                self.in_try = was_in_try;
+            }
+            _ => visitor::walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        self.with_semantic_checker(|checker, context| checker.visit_expr(expr, context));
+        match expr {
+            Expr::Lambda(lambda) => {
+                if let Some(parameters) = &lambda.parameters {
+                    self.visit_parameters(parameters);
+                }
+                let scope = Scope::new(ScopeKind::Lambda, &[], lambda.parameters.as_deref());
+                self.in_scope(scope, |context| context.visit_expr(&lambda.body));
+            }
+            Expr::ListComp(ast::ExprListComp {
+                elt, generators, ..
+            })
+            | Expr::SetComp(ast::ExprSetComp {
+                elt, generators, ..
+            })
+            | Expr::Generator(ast::ExprGenerator {
+                elt, generators, ..
+            }) => {
+                let kind = ScopeKind::Comprehension {
+                    is_async: generators.iter().any(|generator| generator.is_async),
+                    is_generator: expr.is_generator_expr(),
+                };
+                self.visit_comprehension_scope(kind, generators, [&**elt]);
+            }
+            Expr::DictComp(ast::ExprDictComp {
+                key,
+                value,
+                generators,
+                ..
+            }) => {
+                let kind = ScopeKind::Comprehension {
+                    is_async: generators.iter().any(|generator| generator.is_async),
+                    is_generator: false,
+                };
+                self.visit_comprehension_scope(kind, generators, [&**key, &**value]);
+            }
+            _ => visitor::walk_expr(self, expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ruff_python_ast::PythonVersion;
+
+    fn errors(src: &str) -> Vec<String> {
+        let parsed = ruff_python_parser::parse_module(src).unwrap();
+        super::check(src, parsed.suite(), PythonVersion::PY313)
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_scopes() {
+        assert_eq!(
+            errors("for x in y:\n    ls tmp\n    break\n"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            errors("def f():\n    x = 1\n    def g():\n        nonlocal x\n"),
+            Vec::<String>::new()
+        );
+        assert_eq!(errors("break\n").len(), 1);
+        assert_eq!(errors("def f():\n    nonlocal x\n").len(), 1);
+    }
+
+    #[test]
+    fn test_lowered_code() {
+        assert_eq!(
+            errors("async def f():\n    x = $(echo @(await y))\n"),
+            Vec::<String>::new()
+        );
+        assert_eq!(errors("def f():\n    $[echo @(await y)]\n").len(), 1);
+    }
+}
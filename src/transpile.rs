@@ -0,0 +1,286 @@
+//! Generates Python source code from the desugared AST of a xonsh file.
+//!
+//! The generated code is paired with a [`SourceMap`] that links every generated range back to
+//! the range in the original `.xsh` source it was produced from.
+use pyo3::prelude::*;
+use ruff_python_ast::visitor::source_order::{SourceOrderVisitor, TraversalSignal};
+use ruff_python_ast::{AnyNodeRef, NodeKind, Stmt};
+use ruff_python_codegen::{Generator, Indentation};
+use ruff_source_file::{LineEnding, LineIndex, SourceCode};
+use ruff_text_size::{Ranged, TextLen, TextRange, TextSize};
+
+/// A `(lineno, col_offset)` pair, with one-based lines and zero-based columns like Python's `ast`.
+type Position = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    start: Position,
+    end: Position,
+}
+
+impl Span {
+    fn new(source: &SourceCode, range: TextRange) -> Self {
+        Self {
+            start: position(source, range.start()),
+            end: position(source, range.end()),
+        }
+    }
+    fn contains(&self, pos: Position) -> bool {
+        self.start <= pos && pos < self.end
+    }
+    fn as_tuple(&self) -> (usize, usize, usize, usize) {
+        (self.start.0, self.start.1, self.end.0, self.end.1)
+    }
+}
+
+fn position(source: &SourceCode, offset: TextSize) -> Position {
+    let location = source.line_column(offset);
+    (location.line.get(), location.column.to_zero_indexed())
+}
+
+/// Maps ranges of the generated Python code to the ranges of the original xonsh source.
+#[pyclass(module = "xonsh_rd_parser", frozen)]
+#[derive(Debug, Clone)]
+pub(crate) struct SourceMap {
+    file: String,
+    /// `(generated, original)` pairs in the pre-order of the AST, so that a node always comes
+    /// before the nodes nested inside it.
+    mappings: Vec<(Span, Span)>,
+}
+
+impl SourceMap {
+    pub(crate) fn to_json(&self) -> String {
+        let mappings = self
+            .mappings
+            .iter()
+            .map(|(generated, original)| {
+                let (gl, gc, gel, gec) = generated.as_tuple();
+                let (ol, oc, oel, oec) = original.as_tuple();
+                [gl, gc, gel, gec, ol, oc, oel, oec]
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "version": 1,
+            "source": self.file,
+            "mappings": mappings,
+        })
+        .to_string()
+    }
+}
+
+#[pymethods]
+impl SourceMap {
+    /// The name of the original xonsh file.
+    #[getter]
+    fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// `(generated, original)` pairs of `(lineno, col_offset, end_lineno, end_col_offset)` tuples.
+    #[getter]
+    #[allow(clippy::type_complexity)]
+    fn mappings(&self) -> Vec<((usize, usize, usize, usize), (usize, usize, usize, usize))> {
+        self.mappings
+            .iter()
+            .map(|(generated, original)| (generated.as_tuple(), original.as_tuple()))
+            .collect()
+    }
+
+    /// Returns the `(lineno, col_offset)` in the original source for a position in the
+    /// generated code.
+    ///
+    /// Without a column, the outermost node starting on the given line is used, which is what
+    /// a traceback line refers to.
+    #[pyo3(signature = (lineno, col_offset = None))]
+    fn lookup(&self, lineno: usize, col_offset: Option<usize>) -> Option<(usize, usize)> {
+        let found = match col_offset {
+            Some(col) => self
+                .mappings
+                .iter()
+                .rev()
+                .find(|(generated, _)| generated.contains((lineno, col))),
+            None => self
+                .mappings
+                .iter()
+                .find(|(generated, _)| generated.start.0 == lineno)
+                .or_else(|| {
+                    self.mappings.iter().rev().find(|(generated, _)| {
+                        generated.start.0 <= lineno && lineno <= generated.end.0
+                    })
+                }),
+        };
+        found.map(|(_, original)| original.start)
+    }
+
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> String {
+        self.to_json()
+    }
+
+    fn __len__(&self) -> usize {
+        self.mappings.len()
+    }
+}
+
+/// Collects the kind and range of every node in source order.
+#[derive(Default)]
+struct NodeCollector {
+    nodes: Vec<(NodeKind, bool, TextRange)>,
+}
+
+impl NodeCollector {
+    fn collect(stmt: &Stmt) -> Vec<(NodeKind, bool, TextRange)> {
+        let mut collector = Self::default();
+        collector.visit_stmt(stmt);
+        collector.nodes
+    }
+}
+
+impl<'a> SourceOrderVisitor<'a> for NodeCollector {
+    fn enter_node(&mut self, node: AnyNodeRef<'a>) -> TraversalSignal {
+        self.nodes
+            .push((node.kind(), node.is_statement(), node.range()));
+        TraversalSignal::Traverse
+    }
+}
+
+/// Pairs the ranges of the nodes in the `generated` statement with the ones in the `original`.
+///
+/// Both trees are expected to have the same shape. If the expressions differ (e.g. because the
+/// code generator normalised them), only the statements are paired. If even the statements
+/// differ, every generated statement is mapped to the `original` statement as a whole.
+fn pair_nodes(original: &Stmt, generated: &Stmt) -> Vec<(TextRange, TextRange)> {
+    let original = NodeCollector::collect(original);
+    let generated = NodeCollector::collect(generated);
+
+    let same_shape = original.len() == generated.len()
+        && original
+            .iter()
+            .zip(&generated)
+            .all(|((left, ..), (right, ..))| left == right);
+    if same_shape {
+        return generated
+            .iter()
+            .zip(&original)
+            .map(|((.., generated), (.., original))| (*generated, *original))
+            .collect();
+    }
+
+    let statements = |nodes: Vec<(NodeKind, bool, TextRange)>| {
+        nodes
+            .into_iter()
+            .filter_map(|(_, is_statement, range)| is_statement.then_some(range))
+            .collect::<Vec<_>>()
+    };
+    let original = statements(original);
+    let generated = statements(generated);
+    if original.len() == generated.len() {
+        generated.into_iter().zip(original).collect()
+    } else {
+        let outer = original[0];
+        generated.into_iter().map(|range| (range, outer)).collect()
+    }
+}
+
+/// Generates the Python code for the given module body and its source map.
+pub(crate) fn transpile(source: &SourceCode, body: &[Stmt], file: &str) -> (String, SourceMap) {
+    let indentation = Indentation::default();
+    let mut code = String::new();
+    let mut chunks = Vec::with_capacity(body.len());
+
+    for stmt in body {
+        let start = code.text_len();
+        code.push_str(&Generator::new(&indentation, LineEnding::Lf).stmt(stmt));
+        chunks.push(TextRange::new(start, code.text_len()));
+        code.push('\n');
+    }
+
+    // Re-parse the generated code to find out where each of the nodes ended up.
+    let generated = ruff_python_parser::parse_module(&code).ok();
+    let generated_body = generated
+        .as_ref()
+        .map(|parsed| parsed.suite().as_slice())
+        .filter(|suite| suite.len() == body.len());
+
+    let mut pairs = Vec::new();
+    for (idx, (stmt, chunk)) in body.iter().zip(chunks).enumerate() {
+        match generated_body {
+            Some(suite) => pairs.extend(pair_nodes(stmt, &suite[idx])),
+            None => pairs.push((chunk, stmt.range())),
+        }
+    }
+
+    let line_index = LineIndex::from_source_text(&code);
+    let generated_code = SourceCode::new(&code, &line_index);
+    let mappings = pairs
+        .into_iter()
+        .map(|(generated, original)| {
+            (
+                Span::new(&generated_code, generated),
+                Span::new(source, original),
+            )
+        })
+        .collect();
+
+    let source_map = SourceMap {
+        file: file.to_string(),
+        mappings,
+    };
+    (code, source_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pair_nodes, transpile};
+    use ruff_source_file::{LineIndex, SourceCode};
+    use ruff_text_size::{TextRange, TextSize};
+
+    fn transpile_str(src: &str) -> (String, super::SourceMap) {
+        let parsed = ruff_python_parser::parse_module(src).unwrap();
+        let line_index = LineIndex::from_source_text(src);
+        let source = SourceCode::new(src, &line_index);
+        transpile(&source, parsed.suite(), "<test>")
+    }
+
+    #[test]
+    fn test_transpile_procs() {
+        let (code, _) = transpile_str("x = $(ls -l)\nls tmp\n");
+        assert_eq!(
+            code,
+            "x = __xonsh__.cmd('ls', '-l').out()\n__xonsh__.cmd('ls', 'tmp').hide()\n"
+        );
+    }
+
+    #[test]
+    fn test_source_map_lines() {
+        let (_, source_map) = transpile_str("if True:\n\n    echo hello\n    x = $HOME\n");
+        assert_eq!(source_map.lookup(1, None), Some((1, 0)));
+        assert_eq!(source_map.lookup(2, None), Some((3, 4)));
+        assert_eq!(source_map.lookup(3, None), Some((4, 4)));
+    }
+
+    #[test]
+    fn test_source_map_statement_count_mismatch() {
+        let original = ruff_python_parser::parse_module("if x:\n    a\n").unwrap();
+        let generated = ruff_python_parser::parse_module("if x:\n    a\n    b\n").unwrap();
+        let pairs = pair_nodes(&original.suite()[0], &generated.suite()[0]);
+
+        let outer = TextRange::new(TextSize::new(0), TextSize::new(11));
+        assert_eq!(
+            pairs,
+            vec![
+                (TextRange::new(TextSize::new(0), TextSize::new(17)), outer),
+                (TextRange::new(TextSize::new(10), TextSize::new(11)), outer),
+                (TextRange::new(TextSize::new(16), TextSize::new(17)), outer),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_source_map_semicolons() {
+        let (code, source_map) = transpile_str("a; b\n");
+        assert_eq!(code, "a\nb\n");
+        assert_eq!(source_map.lookup(1, None), Some((1, 0)));
+        assert_eq!(source_map.lookup(2, None), Some((1, 3)));
+    }
+}
@@ -0,0 +1,8 @@
+import ast
+
+
+def test_semicolon_inside_brackets_is_an_argument():
+    from xonsh_rd_parser import Parser
+
+    tree = Parser("$[echo ;]").parse()
+    assert "Constant(value=';')" in ast.dump(tree)
@@ -0,0 +1,37 @@
+import pytest
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        ("$[cp file.{txt,bak} dir]", ["cp", ["file.txt", "file.bak"], "dir"]),
+        ("$[touch log{1..3}]", ["touch", ["log1", "log2", "log3"]]),
+        ("$[touch {01..03}.txt]", ["touch", ["01.txt", "02.txt", "03.txt"]]),
+        ("$[echo {c..a}]", ["echo", ["c", "b", "a"]]),
+        ("$[echo {a,b}{1,2}]", ["echo", ["a1", "a2", "b1", "b2"]]),
+        ("$[echo x{a,b{1,2}}]", ["echo", ["xa", "xb1", "xb2"]]),
+        ("$[echo {} {a}]", ["echo", "{}", "{a}"]),
+        ("$[echo '{a,b}']", ["echo", "{a,b}"]),
+    ],
+)
+def test_brace_expansion(inp, exp, cmd):
+    assert cmd(inp) == exp
+
+
+def test_brace_expansion_with_dynamic_parts(cmd):
+    assert cmd("$[ls $HOME/{a,b}]", xenv={"HOME": "/h"}) == ["ls", ["/h/a", "/h/b"]]
+    assert cmd("$[ls {$A,b}/x]", xenv={"A": "a"}) == ["ls", ["a/x", "b/x"]]
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        ("$[cp file.{txt,bak} dir]", "$[cp file.{txt,bak} dir]\n"),
+        ("$[touch log{1..3}.txt]", "$[touch log{1,2,3}.txt]\n"),
+        ("x = ['a', 'b']", "x = ['a', 'b']\n"),
+    ],
+)
+def test_unparse_brace_expansion(inp, exp):
+    from xonsh_rd_parser import Parser
+
+    assert Parser(inp).unparse() == exp
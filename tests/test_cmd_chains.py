@@ -0,0 +1,15 @@
+import pytest
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        ("make && make install || echo failed", "![make && make install || echo failed]\n"),
+        ("$[a and b]", "$[a && b]\n"),
+        ("x = @$(which -a python || which python3)", "x = @$(which -a python || which python3)\n"),
+    ],
+)
+def test_unparse_cmd_chains(inp, exp):
+    from xonsh_rd_parser import Parser
+
+    assert Parser(inp).unparse() == exp
@@ -0,0 +1,30 @@
+import ast
+
+import pytest
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        ("FOO=bar BAZ=1 make test", "![FOO=bar BAZ=1 make test]\n"),
+        ("x = $(FOO=$HOME ls)", "x = $(FOO=$HOME ls)\n"),
+        ("![FOO='a b' BAR=@(x) env | grep FOO]", "![FOO='a b' BAR=@(x) env | grep FOO]\n"),
+        ("$[FOO= make]", "$[FOO= make]\n"),
+    ],
+)
+def test_env_prefixed_commands(inp, exp):
+    from xonsh_rd_parser import Parser
+
+    assert "keyword(arg='env'" in ast.dump(Parser(inp).parse())
+    assert Parser(inp).unparse() == exp
+
+
+@pytest.mark.parametrize(
+    "inp",
+    ["x=1", "x=f(a, b)", "x=a if b else c", "x=y or z", "x=[1, 2]", "x=1  # c"],
+)
+def test_assignments_stay_python(inp):
+    from xonsh_rd_parser import Parser
+
+    tree = Parser(inp).parse()
+    assert isinstance(tree.body[0], ast.Assign)
@@ -0,0 +1,65 @@
+import pytest
+
+
+@pytest.fixture
+def fmt():
+    from xonsh_rd_parser import Parser
+
+    def factory(text: str):
+        return Parser(text, "script.xsh").format()
+
+    return factory
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        ("x=$( ls|grep  'wakka' )", 'x = $(ls | grep wakka)\n'),
+        ("ls  tmp-dir  2>err.txt   &", "ls tmp-dir 2> err.txt &\n"),
+        ("![echo  @( x )   $HOME  'a b']", "![echo @(x) $HOME 'a b']\n"),
+        ("$[ls \\\n    tmp]", "$[ls tmp]\n"),
+        ("print('hi')  # greet", 'print("hi")  # greet\n'),
+        ("f!(x,  y )", "f!(x, y)\n"),
+        (
+            "import os\ndef f():\n    #body\n    return 1\nx = 1\n",
+            "import os\n\n\ndef f():\n    # body\n    return 1\n\n\nx = 1\n",
+        ),
+        (
+            "with! x:\n      some   text # kept\n        more\n",
+            "with! x:\n    some   text # kept\n      more\n",
+        ),
+        ("x = [\n    1,\n    2,\n]\n", "x = [1, 2]\n"),
+        ("x = [\n    1,  # one\n    2,\n]\n", "x = [\n    1,  # one\n    2,\n]\n"),
+        (
+            "def f(\n    a,  # first\n    b,\n):\n    return a\n",
+            "def f(\n    a,  # first\n    b,\n):\n    return a\n",
+        ),
+        (
+            "if x:\n  pass\nelif (a and  # why\n      b):\n  pass\n",
+            "if x:\n    pass\nelif (a and  # why\n      b):\n    pass\n",
+        ),
+        ("@dec\ndef f():  #c\n    pass\n", "@dec\ndef f():  # c\n    pass\n"),
+    ],
+)
+def test_format(inp, exp, fmt):
+    assert fmt(inp) == exp
+    assert fmt(exp) == exp
+
+
+def test_format_syntax_error(fmt):
+    with pytest.raises(SyntaxError):
+        fmt("x = (\n")
+
+
+def test_cli_fmt(tmp_path, capsys):
+    from xonsh_rd_parser import main
+
+    script = tmp_path / "script.xsh"
+    script.write_text("x=$(ls  tmp)\n")
+    assert main(["fmt", "--check", str(script)]) == 1
+    assert "would reformat" in capsys.readouterr().out
+    assert script.read_text() == "x=$(ls  tmp)\n"
+
+    assert main(["fmt", str(script)]) == 0
+    assert script.read_text() == "x = $(ls tmp)\n"
+    assert main(["fmt", "--check", str(script)]) == 0
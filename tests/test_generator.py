@@ -0,0 +1,38 @@
+import ast
+
+import pytest
+from yaml_snaps import yaml_line_items
+
+
+@pytest.fixture
+def xonsh_source():
+    from xonsh_rd_parser import Parser
+
+    def factory(text: str):
+        return Parser(text).unparse()
+
+    return factory
+
+
+@pytest.mark.parametrize("inp, exp", yaml_line_items("exprs", "stmts"))
+def test_round_trip(inp, exp, xonsh_source, parse_string):
+    code = xonsh_source(inp)
+    assert ast.dump(parse_string(code)) == ast.dump(parse_string(inp)), code
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        ("x = $(ls -l | grep wakka)", "x = $(ls -l | grep wakka)\n"),
+        ("ls tmp-dir > out.txt &", "![ls tmp-dir > out.txt &]\n"),
+        ("![echo @(x) @$(which python) $HOME]", "![echo @(x) @$(which python) $HOME]\n"),
+        ("print(${x + 'PATH'})", "print(${x + 'PATH'})\n"),
+        ("x = p'/tmp' / g`*.py`", "x = p'/tmp' / g`*.py`\n"),
+        ("range?.index??", "range?.index??\n"),
+        ("print(@foo`.*`)", "print(@foo`.*`)\n"),
+        ("f!(x, [1, 2])", "f!(x, [1, 2])\n"),
+        ("with! x:\n    some\n      more\n", "with! x:\n    some\n      more\n"),
+    ],
+)
+def test_xonsh_forms(inp, exp, xonsh_source):
+    assert xonsh_source(inp) == exp
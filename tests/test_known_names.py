@@ -0,0 +1,34 @@
+import pytest
+
+
+def parse(src, ctx=None):
+    import ast
+
+    from xonsh_rd_parser import Parser
+
+    return ast.unparse(Parser(src, ctx=ctx).parse())
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        ("ls -l", "__xonsh__.cmd('ls', '-l').hide()"),
+        ("ls", "__xonsh__.cmd('ls').hide()"),
+        ("x -l", "x - l"),
+        ("print(ls)", "print(ls)"),
+        ("print(ls)\nls -l", "print(ls)\n__xonsh__.cmd('ls', '-l').hide()"),
+        ("import os as ls\nls -l", "import os as ls\nls - l"),
+        ("ls = 1\nls -l", "ls = 1\nls - l"),
+        ("def f(ls):\n    ls -l", "def f(ls):\n    ls - l"),
+    ],
+)
+def test_known_names(inp, exp):
+    assert parse(inp, ctx={"x"}) == exp
+
+
+def test_ctx_accepts_dicts():
+    assert parse("ls -l", ctx={"ls": None}) == "ls - l"
+
+
+def test_no_ctx_keeps_python():
+    assert parse("ls -l") == "ls - l"
@@ -47,8 +47,8 @@ def test_atdollar_expression():
     assert lex_input(inp) == snapshot(
         [
             ("AtDollarLParen", "0..3", "@$("),
-            ("Name", "3..8", "which"),
-            ("Name", "9..15", "python"),
+            ("Word", "3..8", "which"),
+            ("Word", "9..15", "python"),
             ("Rpar", "15..16", ")"),
         ]
     )
@@ -67,9 +67,7 @@ def test_not_really_and_pre():
     assert lex_input(inp) == snapshot(
         [
             ("BangLSqb", "0..2", "!["),
-            ("Name", "2..5", "foo"),
-            ("Minus", "5..6", "-"),
-            ("And", "6..9", "and"),
+            ("Word", "2..9", "foo-and"),
             ("Rsqb", "9..10", "]"),
         ]
     )
@@ -81,8 +79,7 @@ def test_not_really_and_post():
         [
             ("BangLSqb", "0..2", "!["),
             ("And", "2..5", "and"),
-            ("Minus", "5..6", "-"),
-            ("Name", "6..9", "bar"),
+            ("Word", "5..9", "-bar"),
             ("Rsqb", "9..10", "]"),
         ]
     )
@@ -93,11 +90,7 @@ def test_not_really_and_pre_post():
     assert lex_input(inp) == snapshot(
         [
             ("BangLSqb", "0..2", "!["),
-            ("Name", "2..5", "foo"),
-            ("Minus", "5..6", "-"),
-            ("And", "6..9", "and"),
-            ("Minus", "9..10", "-"),
-            ("Name", "10..13", "bar"),
+            ("Word", "2..13", "foo-and-bar"),
             ("Rsqb", "13..14", "]"),
         ]
     )
@@ -108,9 +101,7 @@ def test_not_really_or_pre():
     assert lex_input(inp) == snapshot(
         [
             ("BangLSqb", "0..2", "!["),
-            ("Name", "2..5", "foo"),
-            ("Minus", "5..6", "-"),
-            ("Or", "6..8", "or"),
+            ("Word", "2..8", "foo-or"),
             ("Rsqb", "8..9", "]"),
         ]
     )
@@ -122,8 +113,7 @@ def test_not_really_or_post():
         [
             ("BangLSqb", "0..2", "!["),
             ("Or", "2..4", "or"),
-            ("Minus", "4..5", "-"),
-            ("Name", "5..8", "bar"),
+            ("Word", "4..8", "-bar"),
             ("Rsqb", "8..9", "]"),
         ]
     )
@@ -146,11 +136,7 @@ def test_not_really_or_pre_post():
     assert lex_input(inp) == snapshot(
         [
             ("BangLSqb", "0..2", "!["),
-            ("Name", "2..5", "foo"),
-            ("Minus", "5..6", "-"),
-            ("Or", "6..8", "or"),
-            ("Minus", "8..9", "-"),
-            ("Name", "9..12", "bar"),
+            ("Word", "2..12", "foo-or-bar"),
             ("Rsqb", "12..13", "]"),
         ]
     )
@@ -161,19 +147,13 @@ def test_subproc_line_cont_space():
     assert lex_input(inp) == snapshot(
         [
             ("BangLSqb", "0..2", "!["),
-            ("Name", "2..6", "echo"),
-            ("Minus", "7..8", "-"),
-            ("Minus", "8..9", "-"),
-            ("Name", "9..16", "option1"),
-            ("Name", "17..23", "value1"),
-            ("Minus", "31..32", "-"),
-            ("Minus", "32..33", "-"),
-            ("Name", "33..40", "option2"),
-            ("Name", "41..47", "value2"),
-            ("Minus", "55..56", "-"),
-            ("Minus", "56..57", "-"),
-            ("Name", "57..64", "optionZ"),
-            ("Name", "65..71", "valueZ"),
+            ("Word", "2..6", "echo"),
+            ("Word", "7..16", "--option1"),
+            ("Word", "17..23", "value1"),
+            ("Word", "31..40", "--option2"),
+            ("Word", "41..47", "value2"),
+            ("Word", "55..64", "--optionZ"),
+            ("Word", "65..71", "valueZ"),
             ("Rsqb", "71..72", "]"),
         ]
     )
@@ -184,24 +164,32 @@ def test_subproc_line_cont_nospace():
     assert lex_input(inp) == snapshot(
         [
             ("BangLSqb", "0..2", "!["),
-            ("Name", "2..6", "echo"),
-            ("Minus", "7..8", "-"),
-            ("Minus", "8..9", "-"),
-            ("Name", "9..16", "option1"),
-            ("Name", "17..23", "value1"),
-            ("Minus", "30..31", "-"),
-            ("Minus", "31..32", "-"),
-            ("Name", "32..39", "option2"),
-            ("Name", "40..46", "value2"),
-            ("Minus", "53..54", "-"),
-            ("Minus", "54..55", "-"),
-            ("Name", "55..62", "optionZ"),
-            ("Name", "63..69", "valueZ"),
+            ("Word", "2..6", "echo"),
+            ("Word", "7..16", "--option1"),
+            ("Word", "17..23", "value1"),
+            ("Word", "30..39", "--option2"),
+            ("Word", "40..46", "value2"),
+            ("Word", "53..62", "--optionZ"),
+            ("Word", "63..69", "valueZ"),
             ("Rsqb", "69..70", "]"),
         ]
     )
 
 
+def test_shell_words():
+    # the words of a command are lexed like the parser sees them, not as Python
+    inp = "ls --version=1.2.3 a#b it's 08"
+    assert lex_input(inp) == snapshot(
+        [
+            ("Word", "0..2", "ls"),
+            ("Word", "3..18", "--version=1.2.3"),
+            ("Word", "19..22", "a#b"),
+            ("Word", "23..27", "it's"),
+            ("Word", "28..30", "08"),
+        ]
+    )
+
+
 def test_atdollar():
     assert lex_input("@$()") == snapshot(
         [("AtDollarLParen", "0..3", "@$("), ("Rpar", "3..4", ")")]
@@ -0,0 +1,17 @@
+import pytest
+
+
+def test_arguments_keep_their_lines(parse_string):
+    tree = parse_string("x = $(docker run\n    --rm \\\n    image)\n")
+    args = tree.body[0].value.func.value.args
+    assert [(a.value, a.lineno, a.col_offset) for a in args] == [
+        ("docker", 1, 6),
+        ("run", 1, 13),
+        ("--rm", 2, 4),
+        ("image", 3, 4),
+    ]
+
+
+def test_unindented_line_ends_the_command(parse_string):
+    with pytest.raises(SyntaxError):
+        parse_string("x = $(ls\ny = 1\n")
@@ -0,0 +1,61 @@
+import pytest
+
+
+@pytest.fixture
+def lint():
+    from xonsh_rd_parser import Parser
+
+    def factory(text: str, select=None):
+        return [
+            (d.code, d.lineno, d.col_offset)
+            for d in Parser(text, "script.xsh").lint(select)
+        ]
+
+    return factory
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        ("if $(which git):\n    pass\n", [("XSH001", 1, 3)]),
+        ("while not $(pgrep x):\n    pass\n", [("XSH001", 1, 10)]),
+        ("x = 1 if $(ls) else 2", [("XSH001", 1, 9)]),
+        ("if !(which git):\n    pass\n", []),
+        ("$[rm -rf $PREFIX/]", [("XSH002", 1, 9)]),
+        ("$[rm -rf '$PREFIX/']", []),
+        ("$[echo @('a')]", [("XSH003", 1, 9)]),
+        ("$[echo @(x)]", []),
+        ("$[echo hi > a.txt > b.txt]", [("XSH004", 1, 18)]),
+        ("$[echo hi > a.txt 2> b.txt]", []),
+        ("x = $(make build &)", [("XSH005", 1, 17)]),
+        ("![make build &]", []),
+        ("x = $[ls]", [("XSH006", 1, 4)]),
+        ("$[ls]", []),
+        ("if x:\n    $[ls]\nprint($[ls])", [("XSH006", 3, 6)]),
+        ("x = g`file.txt`", [("XSH007", 1, 4)]),
+        ("x = g`*.txt`", []),
+    ],
+)
+def test_rules(inp, exp, lint):
+    assert lint(inp) == exp
+
+
+def test_select(lint):
+    src = "x = $[ls]\ny = g`file.txt`\n"
+    assert lint(src, ["XSH006"]) == [("XSH006", 1, 4)]
+    assert lint(src, ["uncaptured-value-used"]) == [("XSH006", 1, 4)]
+    assert lint(src, ["glob-without-wildcard"]) == [("XSH007", 2, 4)]
+    assert lint(src, ["XSH00"]) == lint(src)
+    with pytest.raises(ValueError):
+        lint(src, ["XSH999"])
+
+
+def test_cli_lint(tmp_path, capsys):
+    from xonsh_rd_parser import main
+
+    script = tmp_path / "script.xsh"
+    script.write_text("x = $[ls]\n")
+    assert main(["lint", str(script)]) == 1
+    assert f"{script}:1:5: XSH006 " in capsys.readouterr().out
+    assert main(["lint", "--select", "XSH001,XSH007", str(script)]) == 0
+    assert main(["lint", "--select", "nope", str(script)]) == 2
@@ -0,0 +1,14 @@
+import pytest
+
+
+@pytest.mark.parametrize(
+    "inp",
+    [
+        "$[cp --out=${d}/x ${'HO' + 'ME'}]",
+        "$[echo a!(which b) ![c] $[d]e]",
+    ],
+)
+def test_unparse_nested_proc_args(inp):
+    from xonsh_rd_parser import Parser
+
+    assert Parser(inp).unparse() == inp + "\n"
@@ -0,0 +1,39 @@
+import random
+
+import pytest
+
+# fmt: off
+FRAGMENTS = [
+    "$(", "$[", "${", "![", "!(", "@(", "@$(", "(", "[", "{", ")", "]", "}",
+    "|", "&", "&&", "||", ";", ">", "2>", "e>o", "@", "@x", "$", "$X",
+    "echo", "ls", "f!", "with! x:", ":", ",", "=", "'s'", '"', "f'{",
+    "\n", "\n    ", "\\\n", " ", "# c", "1", "<(", ">(", "{a,b}", "{1..3}",
+    "{a,", "..", "@json", "@lines", "@.", "@.env", "2>&1", "|&", "o+e>",
+    "\\ ", "--x=1.2", 'a"b"', 'pf"', 'rg"', "pr`", "`", '@f"', "X=1",
+    "|=", " e", "err|",
+]
+# fmt: on
+
+
+def random_sources(count: int):
+    rng = random.Random(1234)
+    for _ in range(count):
+        yield "".join(rng.choices(FRAGMENTS, k=rng.randint(1, 16)))
+
+
+@pytest.mark.parametrize("ctx", [None, ["x"]])
+@pytest.mark.parametrize("src", random_sources(200))
+def test_malformed_source_raises_syntax_error(src, ctx):
+    from xonsh_rd_parser import Parser
+
+    try:
+        Parser(src, ctx=ctx, strict_patterns=ctx is not None).parse()
+    except SyntaxError:
+        pass
+
+
+def test_parse_file_missing(tmp_path):
+    from xonsh_rd_parser import Parser
+
+    with pytest.raises(FileNotFoundError):
+        Parser.parse_file(str(tmp_path / "missing.xsh"))
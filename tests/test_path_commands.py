@@ -0,0 +1,11 @@
+import pytest
+
+
+@pytest.mark.parametrize(
+    "inp",
+    ["ls -l", "a / b", "a /b", "x -1", "a .b", "~x", "x = -y", "a.b -= 1"],
+)
+def test_python_keeps_its_meaning(inp, unparse):
+    import ast
+
+    assert unparse(inp) == ast.unparse(ast.parse(inp))
@@ -0,0 +1,53 @@
+import re
+import warnings
+
+import pytest
+from xonsh_rd_parser import Parser
+
+
+@pytest.mark.parametrize(
+    "src, msg, text",
+    [
+        ("x = `(a|b`", "regex: missing `)`, unterminated group", "("),
+        ("x = `a)`", "regex: unbalanced parenthesis", ")"),
+        (r"x = `\q+`", r"regex: bad escape `\q`", r"\q"),
+        ("x = `[a-z`", "regex: unterminated character set", "["),
+        ("x = `[z-a]`", "regex: bad character range `z-a`", "z-a"),
+        ('x = g"*.[ch"', "glob: unterminated character set", "["),
+        ("$(ls `(foo`)", "regex: missing `)`, unterminated group", "("),
+    ],
+)
+def test_invalid_pattern_is_an_error_in_strict_mode(src, msg, text):
+    with pytest.raises(SyntaxError, match=re.escape(msg)) as exc:
+        Parser(src, strict_patterns=True).parse()
+    col = src.index(text)
+    assert (exc.value.lineno, exc.value.offset) == (1, col + 1)
+
+
+def test_invalid_pattern_is_a_warning():
+    src = "x = 1\ny = `(a`\n"
+    with pytest.warns(SyntaxWarning, match="unterminated group") as record:
+        Parser(src, file_name="script.xsh").parse()
+    [warning] = record
+    assert (warning.filename, warning.lineno) == ("script.xsh", 2)
+
+
+def test_warning_in_with_items_is_reported_once():
+    with pytest.warns(SyntaxWarning, match="unterminated group") as record:
+        Parser("with (`(a` as f, b):\n    pass\n").parse()
+    assert len(record) == 1
+
+
+@pytest.mark.parametrize(
+    "src",
+    [
+        r"x = `(a|b)+[^]x]\d{1,2}`",
+        "x = f`({y})`",
+        'x = g"*.[!ch]"',
+        "x = @foo`(`",
+    ],
+)
+def test_valid_pattern(src):
+    with warnings.catch_warnings():
+        warnings.simplefilter("error")
+        Parser(src, strict_patterns=True).parse()
@@ -0,0 +1,13 @@
+import pytest
+
+
+@pytest.mark.parametrize("inp", ["x = a <(b)", "x = a >(b)", "x = a<(b)"])
+def test_comparisons_stay_python(inp, unparse):
+    assert "process_substitution" not in unparse(inp)
+
+
+def test_unparse_process_substitution():
+    from xonsh_rd_parser import Parser
+
+    src = "$[diff <(sort a|uniq) <(sort b)]"
+    assert Parser(src).unparse() == "$[diff <(sort a | uniq) <(sort b)]\n"
@@ -106,3 +106,40 @@ def test_redirect_output_to_error(r, e, cmd):
             "<": "input.txt",
         },
     ]
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        ("$[make >> build.log]", ["make", {">>": "build.log"}]),
+        ("$[make e>> err.log]", ["make", {"e>>": "err.log"}]),
+        ("$[make 2>> err.log]", ["make", {"2>>": "err.log"}]),
+        ("$[make a> all.log]", ["make", {"a>": "all.log"}]),
+        ("$[make &> all.log]", ["make", {"&>": "all.log"}]),
+        ("$[make &>> all.log]", ["make", {"&>>": "all.log"}]),
+        ("$[cat <> dev.txt]", ["cat", {"<>": "dev.txt"}]),
+        ("$[echo 2 > two.txt]", ["echo", "2", {">": "two.txt"}]),
+    ],
+)
+def test_redirect_operators(inp, exp, cmd):
+    assert cmd(inp) == exp
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        ("$[make > $LOG]", ["make", {">": "log.txt"}]),
+        ("$[make > @(log)]", ["make", {">": ["out.log"]}]),
+        ("$[make > 'my file.txt']", ["make", {">": "my file.txt"}]),
+    ],
+)
+def test_redirect_expression_targets(inp, exp, cmd):
+    assert cmd(inp, xenv={"LOG": "log.txt"}, log="out.log") == exp
+
+
+@pytest.mark.parametrize("inp", ["$[ls 2>&x]", "$[ls 2>>&1]", "$[ls <&0]", "$[ls > | wc]"])
+def test_malformed_redirects(inp):
+    from xonsh_rd_parser import Parser
+
+    with pytest.raises(SyntaxError):
+        Parser(inp).parse()
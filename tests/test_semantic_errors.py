@@ -0,0 +1,56 @@
+import pytest
+
+
+@pytest.fixture
+def parse():
+    from xonsh_rd_parser import Parser
+
+    def factory(text: str):
+        return Parser(text, "script.xsh").parse()
+
+    return factory
+
+
+@pytest.mark.parametrize(
+    "inp",
+    [
+        "return 1",
+        "def f():\n    await x\n",
+        "def f(x, x):\n    pass\n",
+        "match x:\n    case y:\n        pass\n    case 1:\n        pass\n",
+        "def f():\n    $[echo @(await x)]\n",
+        "with! (yield):\n    body\n",
+    ],
+)
+def test_semantic_errors(inp, parse):
+    with pytest.raises(SyntaxError):
+        parse(inp)
+
+
+def test_semantic_error_message(parse):
+    msg = "`return` statement outside of a function"
+    with pytest.raises(SyntaxError, match=msg) as exc:
+        parse("x = 1\nreturn 1\n")
+    assert exc.value.lineno == 2
+
+
+def test_tolerant_reports_all_semantic_errors():
+    from xonsh_rd_parser import Parser
+
+    src = "return 1\ndef f(x, x):\n    await y\n"
+    with pytest.warns(SyntaxWarning) as record:
+        tree = Parser(src, "script.xsh").parse(tolerant=True)
+    assert [warning.lineno for warning in record] == [1, 2, 3]
+    assert len(tree.body) == 2
+
+
+@pytest.mark.parametrize(
+    "inp",
+    [
+        "async def f():\n    $[echo @(await x)]\n",
+        "for x in y:\n    if x:\n        break\n",
+        "def f():\n    with! x:\n        return 1\n",
+    ],
+)
+def test_valid(inp, parse):
+    assert parse(inp)
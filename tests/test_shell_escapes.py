@@ -0,0 +1,24 @@
+import pytest
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        (r"echo a\ b", ["echo", "a b"]),
+        (r"ls My\ Documents", ["ls", "My Documents"]),
+        (r"""grep "foo"'bar'baz x""", ["grep", "foobarbaz", "x"]),
+        (r"echo \$HOME \#x a\|b", ["echo", "$HOME", "#x", "a|b"]),
+        (r"dir C:\Users\me", ["dir", r"C:\Users\me"]),
+        (r"echo it\'s", ["echo", "it's"]),
+        (r"echo --msg='a b'", ["echo", "--msg=a b"]),
+    ],
+)
+def test_escapes_and_quotes(inp, exp, cmd):
+    assert cmd(f"![{inp}]") == exp
+
+
+def test_word_range_keeps_the_raw_text(parse_string):
+    src = r"$(ls My\ Documents)"
+    arg = parse_string(src).body[0].value.func.value.args[1]
+    assert arg.value == "My Documents"
+    assert src[arg.col_offset : arg.end_col_offset] == r"My\ Documents"
@@ -0,0 +1,35 @@
+import pytest
+
+
+@pytest.mark.parametrize(
+    "inp, fix",
+    [
+        ("export FOO=bar\n", "$FOO = 'bar'"),
+        ("if [ -f x ]; then\n    pass\n", "![test -f x]:"),
+        ("while [[ -n $x ]]; do\n    pass\n", "![test -n $x]:"),
+        ("x = $((1 + 2))\n", "(1 + 2)"),
+        ("x = ${VAR:-default}\n", "${...}.get('VAR', 'default')"),
+        ("for f in *.txt; do\n    pass\n", "g'*.txt':"),
+        ("for f in a 'b c'; do\n    pass\n", "['a', 'b c']:"),
+    ],
+)
+def test_shell_syntax_fix(inp, fix):
+    from xonsh_rd_parser import Parser
+
+    with pytest.raises(SyntaxError) as excinfo:
+        Parser(inp).parse()
+    start, end, replacement = excinfo.value.fix
+    assert replacement == fix
+    # the fixed source is valid xonsh
+    Parser(inp[:start] + replacement + inp[end:]).parse()
+
+
+def test_shell_syntax_fix_offsets_count_characters():
+    from xonsh_rd_parser import Parser
+
+    inp = "café = '☕'\nexport FOO=bar\n"
+    with pytest.raises(SyntaxError) as excinfo:
+        Parser(inp).parse()
+    start, end, replacement = excinfo.value.fix
+    assert inp[start:end] == "export FOO=bar"
+    assert inp[:start] + replacement + inp[end:] == "café = '☕'\n$FOO = 'bar'\n"
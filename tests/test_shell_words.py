@@ -0,0 +1,35 @@
+import pytest
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        (
+            "$(pip install --version=1.2.3)",
+            "__xonsh__.cmd('pip', 'install', '--version=1.2.3').out()",
+        ),
+        ("$(echo a#b)", "__xonsh__.cmd('echo', 'a#b').out()"),
+        ("$(echo it's)", "__xonsh__.cmd('echo', \"it's\").out()"),
+        ("$(ls 08 1.2.3)", "__xonsh__.cmd('ls', '08', '1.2.3').out()"),
+        ("$(ls foo(bar) [ab]*)", "__xonsh__.cmd('ls', 'foo(bar)', '[ab]*').out()"),
+        ("![echo a|wc]", "__xonsh__.cmd('echo', 'a').pipe('wc').hide()"),
+        ("echo it's a#b", "__xonsh__.cmd('echo', \"it's\", 'a#b').hide()"),
+        ("$(ssh user@host)", "__xonsh__.cmd('ssh', 'user@host').out()"),
+        ("$(npm i foo@1.2)", "__xonsh__.cmd('npm', 'i', 'foo@1.2').out()"),
+        ("$(git log HEAD@{1})", "__xonsh__.cmd('git', 'log', 'HEAD@{1}').out()"),
+        ("$(echo hi! a!b)", "__xonsh__.cmd('echo', 'hi!', 'a!b').out()"),
+    ],
+)
+def test_shell_words(inp, exp, unparse):
+    assert unparse(inp) == exp
+
+
+def test_comment_after_word(unparse):
+    assert unparse("![echo a #b\n]") == "__xonsh__.cmd('echo', 'a').hide()"
+
+
+def test_closing_bracket_in_word(unparse):
+    # the `]` doesn't close a bracket, so the newline is still inside the call
+    assert unparse("f($(echo a]b),\n  1)") == (
+        "f(__xonsh__.cmd('echo', 'a]b').out(), 1)"
+    )
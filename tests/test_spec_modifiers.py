@@ -0,0 +1,32 @@
+import pytest
+
+
+@pytest.mark.parametrize(
+    "inp, exp",
+    [
+        ("$(@json curl api)", ["curl", "api", ["json"]]),
+        ("$(@unthread @error_raise make)", ["make", ["unthread", "error_raise"]]),
+        ("$[@lines FOO=1 ls > out]", ["ls", {"FOO": "1"}, {">": "out"}, ["lines"]]),
+    ],
+)
+def test_spec_modifiers(inp, exp, cmd):
+    assert cmd(inp) == exp
+
+
+@pytest.mark.parametrize(
+    "inp, msg",
+    [
+        ("x = $(@yaml cat x)", "Unknown subprocess modifier `@yaml`"),
+        ("x = $(curl @json x)", "The `@json` modifier must come before the command"),
+    ],
+)
+def test_invalid_spec_modifiers(inp, msg, parse_string):
+    with pytest.raises(SyntaxError, match=msg):
+        parse_string(inp)
+
+
+def test_unparse_spec_modifiers():
+    from xonsh_rd_parser import Parser
+
+    src = "x = $(@json curl a | @lines FOO=1 jq)"
+    assert Parser(src).unparse() == src + "\n"
@@ -0,0 +1,12 @@
+import pytest
+
+
+@pytest.mark.parametrize("prefix", ["pb", "bu", "pg", "tp", "rR", "ft"])
+def test_invalid_prefix(prefix, parse_string):
+    with pytest.raises(SyntaxError, match=f"Invalid string prefix `{prefix}`"):
+        parse_string(f'{prefix}"x"')
+
+
+def test_invalid_backtick_prefix(parse_string):
+    with pytest.raises(SyntaxError, match="Invalid string prefix `b`"):
+        parse_string("b`x`")
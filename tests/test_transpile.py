@@ -0,0 +1,85 @@
+import ast
+import json
+
+import pytest
+
+
+@pytest.fixture
+def transpile():
+    from xonsh_rd_parser import Parser
+
+    def factory(text: str):
+        return Parser(text, "script.xsh").transpile()
+
+    return factory
+
+
+@pytest.mark.parametrize(
+    "inp",
+    [
+        "x = $(ls -l)",
+        "ls tmp-dir | grep wakka",
+        "with! x:\n    some more\n",
+        "if True:\n    $[echo 1]\nelse:\n    print($HOME)\n",
+    ],
+)
+def test_transpiled_code_matches_ast(inp, transpile, parse_string):
+    code, _ = transpile(inp)
+    assert ast.unparse(ast.parse(code)) == ast.unparse(parse_string(inp))
+
+
+def test_source_map_lookup(transpile):
+    code, source_map = transpile("if True:\n\n    echo hello\n    x = $HOME\n")
+    assert code.splitlines()[1].strip() == "__xonsh__.cmd('echo', 'hello').hide()"
+    assert source_map.file == "script.xsh"
+    assert source_map.lookup(1) == (1, 0)
+    assert source_map.lookup(2) == (3, 4)
+    assert source_map.lookup(3) == (4, 4)
+    assert source_map.lookup(100) is None
+
+
+def test_source_map_json(transpile):
+    _, source_map = transpile("echo hello\n")
+    data = json.loads(source_map.to_json())
+    assert data["source"] == "script.xsh"
+    assert len(data["mappings"]) == len(source_map)
+    assert data["mappings"][0][:6] == [1, 0, 1, 37, 1, 0]
+
+
+def test_cli_transpile(tmp_path):
+    from xonsh_rd_parser import main
+
+    script = tmp_path / "script.xsh"
+    script.write_text("echo hello\n")
+    assert main(["transpile", str(script)]) == 0
+    assert (tmp_path / "script.py").read_text() == (
+        "__xonsh__.cmd('echo', 'hello').hide()\n"
+    )
+    assert json.loads((tmp_path / "script.py.map").read_text())["mappings"]
+
+
+def test_cli_transpile_syntax_error(tmp_path, capsys):
+    from xonsh_rd_parser import main
+
+    script = tmp_path / "script.xsh"
+    script.write_text("x = (\n")
+    assert main(["transpile", str(script)]) == 1
+    assert not (tmp_path / "script.py").exists()
+    err = capsys.readouterr().err
+    assert err.startswith(f"{script}: ")
+    assert f"in {script}:" in err
+
+
+def test_cli_transpile_write_error(tmp_path, capsys):
+    from xonsh_rd_parser import main
+
+    first = tmp_path / "first.xsh"
+    second = tmp_path / "second.xsh"
+    first.write_text("echo one\n")
+    second.write_text("echo two\n")
+    (tmp_path / "first.py").mkdir()
+    assert main(["transpile", str(first), str(second)]) == 1
+    assert "first.py" in capsys.readouterr().err
+    assert (tmp_path / "second.py").read_text() == (
+        "__xonsh__.cmd('echo', 'two').hide()\n"
+    )
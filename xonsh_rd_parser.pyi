@@ -1,4 +1,5 @@
 from ast import AST
+from collections.abc import Iterable
 
 class Token:
     start: int
@@ -13,8 +14,45 @@ class Token:
     @property
     def lexpos(self) -> int: ...
 
+class SourceMap:
+    @property
+    def file(self) -> str: ...
+    @property
+    def mappings(
+        self,
+    ) -> list[tuple[tuple[int, int, int, int], tuple[int, int, int, int]]]: ...
+    def lookup(
+        self, lineno: int, col_offset: int | None = None
+    ) -> tuple[int, int] | None: ...
+    def to_json(self) -> str: ...
+    def __len__(self) -> int: ...
+
+class Diagnostic:
+    @property
+    def code(self) -> str: ...
+    @property
+    def name(self) -> str: ...
+    @property
+    def message(self) -> str: ...
+    @property
+    def range(self) -> tuple[int, int]: ...
+    @property
+    def lineno(self) -> int: ...
+    @property
+    def col_offset(self) -> int: ...
+    @property
+    def end_lineno(self) -> int: ...
+    @property
+    def end_col_offset(self) -> int: ...
+
 class Parser:
-    def __init__(self, src: str, file_name: str | None = None) -> None: ...
+    def __init__(
+        self,
+        src: str,
+        file_name: str | None = None,
+        ctx: Iterable[str] | None = None,
+        strict_patterns: bool = False,
+    ) -> None: ...
     def tokens(self, tolerant=False) -> list[Token]: ...
     def subproc_toks(
         self,
@@ -25,7 +63,12 @@ class Parser:
     ) -> str | None: ...
     @staticmethod
     def parse_file(path: str) -> AST: ...
-    def parse(self) -> AST: ...
+    def parse(self, tolerant: bool = False) -> AST: ...
+    def transpile(self) -> tuple[str, SourceMap]: ...
+    def unparse(self) -> str: ...
+    def format(self) -> str: ...
+    def lint(self, select: list[str] | None = None) -> list[Diagnostic]: ...
     def split(self) -> list[str]: ...
 
+def main(argv: list[str] | None = None) -> int: ...
 def get_big_py_file(lines: int | None = None, file_name: str | None = None) -> str: ...